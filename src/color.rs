@@ -1,6 +1,6 @@
 use derive_more::Display;
 
-#[derive(Clone, Copy, Display)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Display)]
 #[display("{_0:06X}")]
 pub struct Color(pub u32);
 
@@ -9,4 +9,6 @@ impl Color {
     pub const YELLOW: Self = Self(0x888800);
     pub const GREEN: Self = Self(0x22aa22);
     pub const GRAY: Self = Self(0x181818);
+    /// for values that are unavailable, dimmer than the foreground but still readable
+    pub const DIM: Self = Self(0x555555);
 }
@@ -0,0 +1,470 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::net::IpAddr;
+use std::os::fd::{AsRawFd, RawFd};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+use crate::color::Color;
+use crate::component::Fg;
+use crate::component::net::{AUTO, DefaultRoute, NET, NoSuchInterface, PROC_NET};
+use crate::netlink::{self, Message, NLM_F_DUMP, NLM_F_REQUEST, Netlink};
+use crate::sysfs;
+
+const RTM_NEWADDR: u16 = 20;
+const RTM_DELADDR: u16 = 21;
+const RTM_GETADDR: u16 = 22;
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+const IFA_F_DEPRECATED: u8 = 0x20;
+const IFA_F_TENTATIVE: u8 = 0x40;
+const RT_SCOPE_UNIVERSE: u8 = 0;
+const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+
+/// ifaddrmsg
+const HEADER_LEN: usize = 8;
+
+/// how many times reading the notifications is retried after an error, e.g. ENOBUFS after an overflow
+const DRAIN_RETRIES: usize = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, clap::ValueEnum)]
+pub enum IpVersion {
+    #[value(name = "4")]
+    V4,
+    #[value(name = "6")]
+    V6,
+    #[default]
+    Both,
+}
+
+impl IpVersion {
+    fn matches(self, address: &IpAddr) -> bool {
+        match self {
+            Self::V4 => address.is_ipv4(),
+            Self::V6 => address.is_ipv6(),
+            Self::Both => true,
+        }
+    }
+}
+
+/// the global addresses of an interface, e.g. wlan0, or of the interface of the default route with `auto`.
+/// they are re-read when the kernel announces a change, see [`Address::subscribe`]
+pub struct Address {
+    net: PathBuf,
+    interface: Interface,
+    version: IpVersion,
+    mask: bool,
+    socket: Netlink,
+    /// shared with the subscription, set when the addresses may have changed
+    changed: Rc<Cell<bool>>,
+    /// shared with the subscription, the index of the shown interface
+    ifindex: Rc<Cell<Option<u32>>>,
+    subscribed: bool,
+    /// the interface along with its addresses
+    cache: RefCell<Option<(String, Vec<IpAddr>)>>,
+}
+
+enum Interface {
+    Fixed(String),
+    Auto(DefaultRoute),
+}
+
+/// the notifications of address changes, whose fd is polled in the main loop
+pub struct AddressEvents {
+    socket: Netlink,
+    changed: Rc<Cell<bool>>,
+    ifindex: Rc<Cell<Option<u32>>>,
+}
+
+impl AddressEvents {
+    /// reads the pending notifications, and marks the addresses as changed if one is about the shown interface.
+    /// the bar picks the change up on its next update
+    pub fn handle(&self) {
+        let watched = self.ifindex.get();
+        let notify = |datagram: &[u8]| {
+            for (header, payload) in netlink::messages(datagram) {
+                if !matches!(header.kind, RTM_NEWADDR | RTM_DELADDR) || payload.len() < HEADER_LEN {
+                    continue;
+                }
+                let index = u32::from_ne_bytes(payload[4..8].try_into().unwrap());
+                if watched.is_none_or(|watched| watched == index) {
+                    self.changed.set(true);
+                }
+            }
+        };
+
+        for _ in 0..DRAIN_RETRIES {
+            match self.socket.drain(notify) {
+                Ok(()) => return,
+                // notifications were lost
+                Err(_) => self.changed.set(true),
+            }
+        }
+    }
+}
+
+impl AsRawFd for AddressEvents {
+    fn as_raw_fd(&self) -> RawFd {
+        self.socket.as_raw_fd()
+    }
+}
+
+impl Address {
+    /// `mask` hides the host part of the addresses, e.g. for screen sharing
+    pub fn new(interface: &str, version: IpVersion, mask: bool) -> Result<Self, NoSuchInterface> {
+        let socket = Netlink::open(libc::NETLINK_ROUTE).map_err(|_| NoSuchInterface)?;
+        Self::with_socket(
+            Path::new(NET),
+            Path::new(PROC_NET),
+            socket,
+            interface,
+            version,
+            mask,
+        )
+    }
+
+    fn with_socket(
+        net: &Path,
+        proc_net: &Path,
+        socket: Netlink,
+        interface: &str,
+        version: IpVersion,
+        mask: bool,
+    ) -> Result<Self, NoSuchInterface> {
+        let interface = if interface == AUTO {
+            Interface::Auto(DefaultRoute::open(proc_net).map_err(|_| NoSuchInterface)?)
+        } else if net.join(interface).exists() {
+            Interface::Fixed(interface.to_string())
+        } else {
+            return Err(NoSuchInterface);
+        };
+
+        Ok(Self {
+            net: net.to_path_buf(),
+            interface,
+            version,
+            mask,
+            socket,
+            changed: Rc::new(Cell::new(false)),
+            ifindex: Rc::new(Cell::new(None)),
+            subscribed: false,
+            cache: RefCell::new(None),
+        })
+    }
+
+    /// subscribes to the address changes of the kernel. without a subscription, the addresses are re-read every tick
+    pub fn subscribe(&mut self) -> io::Result<AddressEvents> {
+        let socket =
+            Netlink::subscribe(libc::NETLINK_ROUTE, RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR)?;
+        Ok(self.events(socket))
+    }
+
+    fn events(&mut self, socket: Netlink) -> AddressEvents {
+        self.subscribed = true;
+        AddressEvents {
+            socket,
+            changed: self.changed.clone(),
+            ifindex: self.ifindex.clone(),
+        }
+    }
+
+    fn interface(&self) -> Option<String> {
+        match &self.interface {
+            Interface::Fixed(interface) => Some(interface.clone()),
+            Interface::Auto(route) => route.interface(),
+        }
+    }
+
+    /// the addresses of the interface, re-read after a change or when the default route moved
+    fn addresses(&self) -> Option<(String, Vec<IpAddr>)> {
+        let interface = self.interface()?;
+
+        let mut cache = self.cache.borrow_mut();
+        let stale = !self.subscribed
+            || self.changed.replace(false)
+            || cache
+                .as_ref()
+                .is_none_or(|(cached, _)| *cached != interface);
+
+        if stale {
+            let ifindex = fs::read(self.net.join(&interface).join("ifindex"))
+                .ok()
+                .and_then(|ifindex| sysfs::parse(&ifindex));
+            self.ifindex.set(ifindex);
+            let addresses = ifindex
+                .and_then(|ifindex| addresses(&self.socket, ifindex).ok())
+                .unwrap_or_default();
+            *cache = Some((interface, addresses));
+        }
+
+        cache.clone()
+    }
+}
+
+/// the usable global addresses of an interface, IPv4 first
+fn addresses(socket: &Netlink, ifindex: u32) -> io::Result<Vec<IpAddr>> {
+    let request = Message::new(RTM_GETADDR, NLM_F_REQUEST | NLM_F_DUMP).push(&[0; HEADER_LEN]);
+
+    let mut addresses: Vec<_> = socket
+        .request(request)?
+        .iter()
+        .filter(|payload| payload.len() >= HEADER_LEN)
+        .filter_map(|payload| {
+            // family, prefix length, flags, scope, index
+            let (header, attrs) = payload.split_at(HEADER_LEN);
+            let flags = header[2];
+            let scope = header[3];
+            let index = u32::from_ne_bytes(header[4..8].try_into().unwrap());
+
+            if index != ifindex
+                || scope != RT_SCOPE_UNIVERSE
+                || flags & (IFA_F_DEPRECATED | IFA_F_TENTATIVE) != 0
+            {
+                return None;
+            }
+
+            // on point-to-point links IFA_ADDRESS is the peer, so prefer IFA_LOCAL
+            let mut address = None;
+            for (kind, value) in netlink::attrs(attrs) {
+                let parsed = match value.len() {
+                    4 => IpAddr::from(<[u8; 4]>::try_from(value).unwrap()),
+                    16 => IpAddr::from(<[u8; 16]>::try_from(value).unwrap()),
+                    _ => continue,
+                };
+                match kind {
+                    IFA_LOCAL => return Some(parsed),
+                    IFA_ADDRESS => address = Some(parsed),
+                    _ => {}
+                }
+            }
+            address
+        })
+        .collect();
+
+    addresses.sort_by_key(|address| address.is_ipv6());
+    Ok(addresses)
+}
+
+/// keeps the network part, e.g. 10.0.x.x or 2001:db8:…
+fn mask(address: IpAddr) -> String {
+    match address {
+        IpAddr::V4(address) => {
+            let [a, b, _, _] = address.octets();
+            format!("{a}.{b}.x.x")
+        }
+        IpAddr::V6(address) => {
+            let [a, b, ..] = address.segments();
+            format!("{a:x}:{b:x}:…")
+        }
+    }
+}
+
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let addresses = self.addresses().map(|(_, addresses)| addresses);
+        let addresses: Vec<_> = addresses
+            .iter()
+            .flatten()
+            .filter(|address| self.version.matches(address))
+            .collect();
+
+        // the first address of each version
+        let v4 = addresses.iter().find(|address| address.is_ipv4());
+        let v6 = addresses.iter().find(|address| address.is_ipv6());
+        let shown: Vec<_> = v4.into_iter().chain(v6).collect();
+
+        if shown.is_empty() {
+            return write!(f, "{}-{}", Fg(Color::DIM), Fg(crate::FG));
+        }
+
+        for (i, address) in shown.into_iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            if self.mask {
+                write!(f, "{}", mask(**address))?;
+            } else {
+                write!(f, "{address}")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{Ipv4Addr, Ipv6Addr};
+    use std::os::unix::net::UnixDatagram;
+
+    use super::*;
+    use crate::fixture::{self, Responder};
+    use crate::netlink::{NLM_F_MULTI, NLMSG_DONE};
+
+    const RT_SCOPE_LINK: u8 = 253;
+
+    fn ifaddr(family: i32, flags: u8, scope: u8, index: u32, attrs: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut header = vec![family as u8, 24, flags, scope];
+        header.extend(index.to_ne_bytes());
+        attrs
+            .iter()
+            .fold(
+                Message::new(RTM_NEWADDR, NLM_F_MULTI).push(&header),
+                |message, (kind, value)| message.attr(*kind, value),
+            )
+            .answer(0)
+    }
+
+    /// answers address dumps like the kernel
+    fn responder() -> (Netlink, Responder) {
+        let v4 = Ipv4Addr::new(10, 0, 3, 7).octets();
+        let peer = Ipv4Addr::new(10, 0, 3, 1).octets();
+        let v6: Ipv6Addr = "2001:db8:1:2::7".parse().unwrap();
+        let link_local: Ipv6Addr = "fe80::1".parse().unwrap();
+        let tentative: Ipv6Addr = "2001:db8:1:2::8".parse().unwrap();
+        let other = Ipv4Addr::new(192, 168, 1, 2).octets();
+
+        let dump = [
+            ifaddr(
+                libc::AF_INET6,
+                0,
+                RT_SCOPE_LINK,
+                3,
+                &[(IFA_ADDRESS, &link_local.octets())],
+            ),
+            ifaddr(
+                libc::AF_INET6,
+                IFA_F_TENTATIVE,
+                0,
+                3,
+                &[(IFA_ADDRESS, &tentative.octets())],
+            ),
+            ifaddr(libc::AF_INET6, 0, 0, 3, &[(IFA_ADDRESS, &v6.octets())]),
+            ifaddr(
+                libc::AF_INET,
+                0,
+                0,
+                3,
+                &[(IFA_ADDRESS, &peer), (IFA_LOCAL, &v4)],
+            ),
+            ifaddr(libc::AF_INET, 0, 0, 4, &[(IFA_LOCAL, &other)]),
+        ];
+
+        Responder::spawn(move |header, _| {
+            assert_eq!(header.kind, RTM_GETADDR);
+
+            let mut answer = Vec::new();
+            for message in &dump {
+                answer.extend(&message[..8]);
+                answer.extend(header.seq.to_ne_bytes());
+                answer.extend(&message[12..]);
+            }
+            answer.extend(Message::new(NLMSG_DONE, NLM_F_MULTI).answer(header.seq));
+            answer
+        })
+    }
+
+    fn none() -> String {
+        format!("{}-{}", Fg(Color::DIM), Fg(crate::FG))
+    }
+
+    fn sample_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        for (name, ifindex) in [("wlan0", 3), ("enp3s0", 4)] {
+            fixture::attrs(&dir.path().join("net").join(name), &[("ifindex", ifindex)]);
+        }
+        fs::write(
+            dir.path().join("route"),
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT\n\
+             wlan0\t00000000\t0100000A\t0003\t0\t0\t600\t00000000\t0\t0\t0\n",
+        )
+        .unwrap();
+        dir
+    }
+
+    fn with_address(
+        interface: &str,
+        version: IpVersion,
+        mask: bool,
+        test: impl FnOnce(&mut Address),
+    ) {
+        let dir = sample_tree();
+        let (socket, responder) = responder();
+
+        let mut address = Address::with_socket(
+            &dir.path().join("net"),
+            dir.path(),
+            socket,
+            interface,
+            version,
+            mask,
+        )
+        .unwrap();
+        test(&mut address);
+
+        responder.stop();
+    }
+
+    #[test]
+    fn global_addresses() {
+        with_address("wlan0", IpVersion::Both, false, |address| {
+            assert_eq!(address.to_string(), "10.0.3.7 2001:db8:1:2::7");
+        });
+        with_address("enp3s0", IpVersion::V6, false, |address| {
+            assert_eq!(address.to_string(), none());
+        });
+        with_address("auto", IpVersion::V4, false, |address| {
+            assert_eq!(address.to_string(), "10.0.3.7");
+        });
+    }
+
+    #[test]
+    fn masked() {
+        with_address("wlan0", IpVersion::Both, true, |address| {
+            assert_eq!(address.to_string(), "10.0.x.x 2001:db8:…");
+        });
+    }
+
+    #[test]
+    fn refreshed_on_change() {
+        with_address("enp3s0", IpVersion::V4, false, |address| {
+            let (ours, theirs) = UnixDatagram::pair().unwrap();
+            ours.set_nonblocking(true).unwrap();
+            let events = address.events(Netlink::from_fd(ours.into()));
+
+            assert_eq!(address.to_string(), "192.168.1.2");
+
+            // without a notification, even a stale cache is kept
+            *address.cache.borrow_mut() = Some(("enp3s0".to_string(), Vec::new()));
+            events.handle();
+            assert_eq!(address.to_string(), none());
+
+            // a change of another interface
+            theirs.send(&ifaddr(libc::AF_INET, 0, 0, 3, &[])).unwrap();
+            events.handle();
+            assert_eq!(address.to_string(), none());
+
+            theirs.send(&ifaddr(libc::AF_INET, 0, 0, 4, &[])).unwrap();
+            events.handle();
+            assert_eq!(address.to_string(), "192.168.1.2");
+        });
+    }
+
+    #[test]
+    fn unknown_interface() {
+        let dir = sample_tree();
+        let (ours, _theirs) = UnixDatagram::pair().unwrap();
+        let address = Address::with_socket(
+            &dir.path().join("net"),
+            dir.path(),
+            Netlink::from_fd(ours.into()),
+            "eth0",
+            IpVersion::Both,
+            false,
+        );
+        assert!(address.is_err());
+    }
+}
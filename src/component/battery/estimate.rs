@@ -0,0 +1,136 @@
+use super::State;
+
+/// how much the newest sample affects the average draw
+const ALPHA: f64 = 0.3;
+
+/// samples needed since the last state transition before an estimate is given
+pub const MIN_SAMPLES: u32 = 3;
+
+/// below this rate (µA or µW) the battery is considered idle
+const MIN_RATE: f64 = 1.;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Estimate {
+    /// not enough samples yet, or the draw is too low to estimate
+    Calculating,
+
+    /// time left, in minutes
+    Minutes(u64),
+}
+
+/// estimates the time left for charging/discharging using an exponentially weighted moving
+/// average of the draw, which is reset whenever the battery state changes
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Estimator {
+    state: Option<State>,
+    rate: f64,
+    samples: u32,
+}
+
+impl Estimator {
+    /// `rate` is the current draw (µA or µW), and `amount` is what's left to charge/discharge
+    /// (µAh or µWh respectively)
+    pub fn sample(&mut self, state: State, rate: u64, amount: u64) -> Estimate {
+        if self.state != Some(state) {
+            *self = Self {
+                state: Some(state),
+                ..Self::default()
+            };
+        }
+
+        let rate = rate as f64;
+        self.rate = if self.samples == 0 {
+            rate
+        } else {
+            ALPHA * rate + (1. - ALPHA) * self.rate
+        };
+        self.samples = self.samples.saturating_add(1);
+
+        if self.samples < MIN_SAMPLES || self.rate < MIN_RATE {
+            return Estimate::Calculating;
+        }
+
+        let minutes = amount as f64 * 60. / self.rate;
+        Estimate::Minutes(minutes as u64)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::prelude::*;
+
+    use super::*;
+
+    fn state() -> impl Strategy<Value = State> {
+        prop_oneof![Just("+"), Just("-")]
+    }
+
+    proptest! {
+        #[test]
+        fn never_panics(samples in prop::collection::vec((state(), any::<u64>(), any::<u64>()), 0..50)) {
+            let mut estimator = Estimator::default();
+            for (state, rate, amount) in samples {
+                estimator.sample(state, rate, amount);
+            }
+        }
+
+        #[test]
+        fn zero_draw_is_calculating(state in state(), amount in any::<u64>(), count in 1..20usize) {
+            let mut estimator = Estimator::default();
+            for _ in 0..count {
+                prop_assert_eq!(estimator.sample(state, 0, amount), Estimate::Calculating);
+            }
+        }
+
+        #[test]
+        fn calculating_until_enough_samples(
+            state in state(),
+            rate in 1u64..10_000_000,
+            amount in 0u64..100_000_000,
+        ) {
+            let mut estimator = Estimator::default();
+            for _ in 1..MIN_SAMPLES {
+                prop_assert_eq!(estimator.sample(state, rate, amount), Estimate::Calculating);
+            }
+            prop_assert_ne!(estimator.sample(state, rate, amount), Estimate::Calculating);
+        }
+
+        #[test]
+        fn constant_draw(state in state(), rate in 1u64..10_000_000, amount in 0u64..100_000_000) {
+            let mut estimator = Estimator::default();
+            let mut estimate = Estimate::Calculating;
+            for _ in 0..MIN_SAMPLES {
+                estimate = estimator.sample(state, rate, amount);
+            }
+
+            let expected = amount * 60 / rate;
+            let Estimate::Minutes(minutes) = estimate else {
+                return Err(TestCaseError::fail("expected an estimate"));
+            };
+            prop_assert!(minutes.abs_diff(expected) <= 1);
+        }
+
+        #[test]
+        fn transition_resets(rate in 1u64..10_000_000, amount in 0u64..100_000_000) {
+            let mut estimator = Estimator::default();
+            for _ in 0..MIN_SAMPLES {
+                estimator.sample("-", rate, amount);
+            }
+            prop_assert_eq!(estimator.sample("+", rate, amount), Estimate::Calculating);
+        }
+
+        #[test]
+        fn smooths_spikes(rate in 1000u64..10_000_000, amount in 0u64..100_000_000) {
+            let mut estimator = Estimator::default();
+            for _ in 0..MIN_SAMPLES {
+                estimator.sample("-", rate, amount);
+            }
+
+            // a single 10x spike only moves the average draw to 3.7x (with ALPHA = 0.3)
+            let Estimate::Minutes(minutes) = estimator.sample("-", rate * 10, amount) else {
+                return Err(TestCaseError::fail("expected an estimate"));
+            };
+            prop_assert!(minutes >= amount * 60 / (rate * 4));
+        }
+    }
+}
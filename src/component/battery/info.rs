@@ -0,0 +1,100 @@
+use std::path::Path;
+use std::{fmt, fs};
+
+use super::pack::{self, Pack};
+use super::{NoSuchBattery, POWER_SUPPLY};
+
+/// everything known about the batteries, for the `battery-info` subcommand
+pub struct BatteryInfo {
+    packs: Vec<Pack>,
+}
+
+impl BatteryInfo {
+    /// `names` are the batteries in /sys/class/power_supply, or `all` (or empty)
+    pub fn new(names: &[String]) -> Result<Self, NoSuchBattery> {
+        let packs = pack::resolve(Path::new(POWER_SUPPLY), names)
+            .iter()
+            .map(|path| Pack::open(path))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| NoSuchBattery)?;
+
+        if packs.is_empty() {
+            return Err(NoSuchBattery);
+        }
+
+        Ok(Self { packs })
+    }
+}
+
+impl fmt::Display for BatteryInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, pack) in self.packs.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+
+            let path = pack.path();
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            writeln!(f, "{}", name)?;
+
+            let raw = |file| fs::read_to_string(path.join(file)).ok();
+            for (title, file) in [
+                ("manufacturer", "manufacturer"),
+                ("model", "model_name"),
+                ("technology", "technology"),
+                ("status", "status"),
+            ] {
+                if let Some(value) = raw(file) {
+                    writeln!(f, "  {:<18} {}", title, value.trim())?;
+                }
+            }
+
+            let Ok(reading) = pack.read() else {
+                writeln!(f, "  unable to read the battery")?;
+                continue;
+            };
+
+            let details = pack.details();
+            let unit = pack.family().unit();
+            let amount = |value: u64| format!("{:.2} {}", value as f64 / 1_000_000., unit);
+
+            writeln!(f, "  {:<18} {}%", "capacity", reading.capacity)?;
+            writeln!(f, "  {:<18} {}", "now", amount(reading.now))?;
+            writeln!(f, "  {:<18} {}", "full", amount(reading.full))?;
+
+            if let Some(full_design) = details.full_design {
+                writeln!(f, "  {:<18} {}", "full (design)", amount(full_design))?;
+            }
+
+            if let Some(health) = health(reading.full, details.full_design) {
+                writeln!(f, "  {:<18} {}%", "health", health)?;
+                writeln!(
+                    f,
+                    "  {:<18} {}%",
+                    "wear level",
+                    100u64.saturating_sub(health)
+                )?;
+            }
+
+            if let Some(cycle_count) = details.cycle_count {
+                writeln!(f, "  {:<18} {}", "cycle count", cycle_count)?;
+            }
+
+            if let Some(start) = details.start_threshold {
+                writeln!(f, "  {:<18} {}%", "start threshold", start)?;
+            }
+
+            if let Some(end) = details.end_threshold {
+                writeln!(f, "  {:<18} {}%", "end threshold", end)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// `full` out of `full_design`, in percents
+pub fn health(full: u64, full_design: Option<u64>) -> Option<u64> {
+    let full_design = full_design.filter(|&design| design > 0)?;
+    Some(full * 100 / full_design)
+}
@@ -0,0 +1,657 @@
+use std::cell::Cell;
+use std::fmt;
+use std::path::Path;
+
+use crate::color::Color;
+use crate::component::Fg;
+use crate::component::hook::Hooks;
+
+mod estimate;
+mod info;
+mod pack;
+mod power;
+
+use estimate::{Estimate, Estimator};
+pub use info::BatteryInfo;
+use pack::{Pack, Reading};
+pub use power::Power;
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// one or more battery packs, shown as a single battery
+pub struct Battery {
+    packs: Vec<Pack>,
+    /// whether the packs report in different units, and have to be converted to energy
+    mixed: bool,
+    show_each: bool,
+    detail: bool,
+    estimator: Cell<Estimator>,
+    hooks: Hooks,
+}
+
+impl Battery {
+    /// `names` are the batteries in /sys/class/power_supply, or `all` to detect every battery that
+    /// powers the system. `show_each` also shows the capacity of every pack, and `detail` shows
+    /// the health, cycle count and charge thresholds.
+    pub fn new(names: &[String], show_each: bool, detail: bool) -> Result<Self, NoSuchBattery> {
+        Self::in_dir(Path::new(POWER_SUPPLY), names, show_each, detail)
+    }
+
+    fn in_dir(
+        dir: &Path,
+        names: &[String],
+        show_each: bool,
+        detail: bool,
+    ) -> Result<Self, NoSuchBattery> {
+        let packs = pack::resolve(dir, names)
+            .iter()
+            .map(|path| Pack::open(path))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| NoSuchBattery)?;
+
+        let Some(first) = packs.first() else {
+            return Err(NoSuchBattery);
+        };
+
+        let mixed = packs.iter().any(|pack| pack.family() != first.family());
+
+        Ok(Self {
+            packs,
+            mixed,
+            show_each,
+            detail,
+            estimator: Cell::default(),
+            hooks: Hooks::default(),
+        })
+    }
+
+    /// `hooks` are fired with the combined capacity, while discharging
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    /// sums up all of the packs into a single reading
+    fn total(&self) -> Option<Reading> {
+        let readings: Vec<_> = self
+            .packs
+            .iter()
+            .filter_map(|pack| {
+                if self.mixed {
+                    pack.read_energy().ok()
+                } else {
+                    pack.read().ok()
+                }
+            })
+            .collect();
+
+        let mut total: Option<Reading> = None;
+        for reading in &readings {
+            let Some(total) = total.as_mut() else {
+                total = Some(*reading);
+                continue;
+            };
+
+            total.state = combine(total.state, reading.state);
+            total.now += reading.now;
+            total.full += reading.full;
+            total.target += reading.target;
+        }
+
+        let mut total = total?;
+
+        // a pack that charges while another one discharges doesn't make the battery last longer
+        total.rate = readings
+            .iter()
+            .filter(|reading| reading.state == total.state)
+            .filter_map(|reading| reading.rate)
+            .reduce(|a, b| a + b);
+
+        if self.packs.len() > 1 && total.full > 0 {
+            total.capacity = (total.now * 100 / total.full).min(100) as u8;
+        }
+
+        Some(total)
+    }
+
+    /// the combined health in percents, the highest cycle count, and the charge thresholds of the
+    /// first pack that has them
+    fn details(&self) -> (Option<u64>, Option<u64>, Option<(u8, u8)>) {
+        let mut full = 0;
+        let mut full_design = 0;
+        let mut cycle_count = None;
+        let mut thresholds = None;
+
+        for pack in &self.packs {
+            let details = pack.details();
+
+            if let (Ok(reading), Some(design)) = (pack.read(), details.full_design) {
+                if self.mixed {
+                    // scale the design capacity to energy, the same way as the full capacity
+                    let energy = pack.read_energy().map_or(0, |reading| reading.full);
+                    full += energy;
+                    full_design += design * energy / reading.full.max(1);
+                } else {
+                    full += reading.full;
+                    full_design += design;
+                }
+            }
+
+            cycle_count = cycle_count.max(details.cycle_count);
+
+            if thresholds.is_none() {
+                thresholds = details
+                    .end_threshold
+                    .map(|end| (details.start_threshold.unwrap_or(0), end));
+            }
+        }
+
+        (
+            info::health(full, Some(full_design)),
+            cycle_count,
+            thresholds,
+        )
+    }
+
+    fn get_estimate(&self, total: &Reading) -> Option<Estimate> {
+        let rate = total.rate?;
+
+        let amount = match total.state {
+            "+" => total.target.saturating_sub(total.now),
+            "-" => total.now,
+            _ => {
+                self.estimator.take();
+                return None;
+            }
+        };
+
+        let mut estimator = self.estimator.get();
+        let estimate = estimator.sample(total.state, rate, amount);
+        self.estimator.set(estimator);
+        Some(estimate)
+    }
+}
+
+impl fmt::Display for Battery {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some(total) = self.total() else {
+            return write!(f, "?");
+        };
+
+        // a low battery only matters while it's discharging
+        if total.state == "-" {
+            self.hooks.update(total.capacity as f32);
+        } else {
+            self.hooks.observe(total.capacity as f32);
+        }
+
+        let state = total.state;
+        let state_color = state_color(state);
+
+        let capacity_color = if total.capacity <= 30 && state != "+" {
+            Color::RED
+        } else {
+            crate::FG
+        };
+
+        match self.get_estimate(&total) {
+            Some(Estimate::Minutes(minutes)) => {
+                let hours = (minutes / 60).min(99);
+                let minutes = minutes % 60;
+                write!(f, "{:02}:{:02} ", hours, minutes)?;
+            }
+
+            Some(Estimate::Calculating) => write!(f, "--:-- ")?,
+
+            None => {}
+        }
+
+        write!(
+            f,
+            "{}{}{}{}",
+            Fg(state_color),
+            state,
+            Fg(capacity_color),
+            total.capacity
+        )?;
+
+        if self.detail {
+            let (health, cycle_count, thresholds) = self.details();
+            if let Some(health) = health {
+                write!(f, " {}{}{:3}%", crate::label("HP "), Fg(crate::FG), health)?;
+            }
+
+            if let Some(cycle_count) = cycle_count {
+                write!(
+                    f,
+                    " {}{}{}",
+                    crate::label("CYC "),
+                    Fg(crate::FG),
+                    cycle_count
+                )?;
+            }
+
+            if let Some((start, end)) = thresholds {
+                write!(
+                    f,
+                    " {}{}{}-{}",
+                    crate::label("LIM "),
+                    Fg(crate::FG),
+                    start,
+                    end
+                )?;
+            }
+        }
+
+        if self.show_each && self.packs.len() > 1 {
+            write!(f, "{}", Fg(crate::FG))?;
+            for (i, pack) in self.packs.iter().enumerate() {
+                let separator = if i == 0 { " " } else { "/" };
+                match pack.read() {
+                    Ok(reading) => write!(f, "{}{}", separator, reading.capacity)?,
+                    Err(_) => write!(f, "{}?", separator)?,
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+type State = &'static str;
+
+/// the state of two packs shown as one
+fn combine(a: State, b: State) -> State {
+    match (a, b) {
+        ("+", _) | (_, "+") => "+",
+        ("-", _) | (_, "-") => "-",
+        ("o", "o") => "o",
+        _ => "?",
+    }
+}
+
+fn state_color(state: State) -> Color {
+    match state {
+        "+" => Color::GREEN,
+        "-" => Color::RED,
+        _ => crate::FG,
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("no such battery")]
+pub struct NoSuchBattery;
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::pack::Family;
+    use super::*;
+    use crate::fixture;
+
+    pub fn supply(root: &Path, name: &str, files: &[(&str, &str)]) {
+        fixture::attrs(&root.join(name), files);
+    }
+
+    fn battery(root: &Path, names: &[&str]) -> Result<Battery, NoSuchBattery> {
+        let names: Vec<_> = names.iter().map(|name| name.to_string()).collect();
+        Battery::in_dir(root, &names, true, false)
+    }
+
+    const COMMON: &[(&str, &str)] = &[("capacity", "50"), ("type", "Battery")];
+
+    fn settle(battery: &Battery) -> Option<Estimate> {
+        (0..estimate::MIN_SAMPLES)
+            .map(|_| battery.get_estimate(&battery.total().unwrap()))
+            .last()
+            .flatten()
+    }
+
+    #[test]
+    fn charge_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                COMMON,
+                &[
+                    ("status", "Discharging"),
+                    ("charge_now", "2000000"),
+                    ("charge_full", "4000000"),
+                    ("current_now", "1000000"),
+                ],
+            ]
+            .concat(),
+        );
+
+        let path = dir.path().join("BAT0");
+        assert_eq!(Family::detect(&path), Some(Family::Charge));
+        let battery = battery(dir.path(), &["BAT0"]).unwrap();
+        assert_eq!(settle(&battery), Some(Estimate::Minutes(120)));
+        assert!(battery.to_string().starts_with("02:00 "));
+    }
+
+    #[test]
+    fn energy_layout() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                COMMON,
+                &[
+                    ("status", "Charging"),
+                    ("energy_now", "30000000"),
+                    ("energy_full", "60000000"),
+                    ("power_now", "20000000"),
+                    ("charge_control_end_threshold", "80"),
+                ],
+            ]
+            .concat(),
+        );
+
+        let path = dir.path().join("BAT0");
+        assert_eq!(Family::detect(&path), Some(Family::Energy));
+        let battery = battery(dir.path(), &["BAT0"]).unwrap();
+
+        // (60 * 80% - 30) Wh / 20 W = 54 minutes
+        assert_eq!(settle(&battery), Some(Estimate::Minutes(54)));
+        assert!(battery.to_string().starts_with("00:54 "));
+    }
+
+    #[test]
+    fn missing_rate() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                COMMON,
+                &[
+                    ("status", "Discharging"),
+                    ("energy_now", "30000000"),
+                    ("energy_full", "60000000"),
+                ],
+            ]
+            .concat(),
+        );
+
+        let battery = battery(dir.path(), &["BAT0"]).unwrap();
+        assert_eq!(settle(&battery), None);
+        assert!(battery.to_string().ends_with("50"));
+    }
+
+    #[test]
+    fn negative_current() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                COMMON,
+                &[
+                    ("status", "Discharging"),
+                    ("charge_now", "2000000"),
+                    ("charge_full", "4000000"),
+                    ("current_now", "-1000000"),
+                ],
+            ]
+            .concat(),
+        );
+
+        let battery = battery(dir.path(), &["BAT0"]).unwrap();
+        assert_eq!(settle(&battery), Some(Estimate::Minutes(120)));
+    }
+
+    #[test]
+    fn zero_current() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                COMMON,
+                &[
+                    ("status", "Discharging"),
+                    ("charge_now", "2000000"),
+                    ("charge_full", "4000000"),
+                    ("current_now", "0"),
+                ],
+            ]
+            .concat(),
+        );
+
+        let battery = battery(dir.path(), &["BAT0"]).unwrap();
+        assert_eq!(settle(&battery), Some(Estimate::Calculating));
+        assert!(battery.to_string().starts_with("--:-- "));
+    }
+
+    #[test]
+    fn no_charge_attributes() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[COMMON, &[("status", "Full")]].concat(),
+        );
+        assert!(battery(dir.path(), &["BAT0"]).is_err());
+    }
+
+    #[test]
+    fn multiple_packs() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "100"),
+                ("status", "Unknown"),
+                ("energy_now", "20000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "0"),
+            ],
+        );
+        supply(
+            dir.path(),
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+
+        let battery = battery(dir.path(), &["BAT0", "BAT1"]).unwrap();
+
+        // 30 Wh / 10 W = 3 hours
+        assert_eq!(settle(&battery), Some(Estimate::Minutes(180)));
+
+        // 30 Wh out of 40 Wh
+        let total = battery.total().unwrap();
+        assert_eq!(total.state, "-");
+        assert_eq!(total.capacity, 75);
+        assert!(battery.to_string().ends_with("75%{F:888888} 100/50"));
+    }
+
+    #[test]
+    fn mixed_families() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("charge_now", "1000000"),
+                ("charge_full", "2000000"),
+                ("current_now", "1000000"),
+                ("voltage_now", "10000000"),
+            ],
+        );
+        supply(
+            dir.path(),
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+
+        let battery = battery(dir.path(), &["BAT0", "BAT1"]).unwrap();
+
+        // 1 Ah * 10 V = 10 Wh, so 20 Wh / 20 W = 1 hour
+        assert_eq!(settle(&battery), Some(Estimate::Minutes(60)));
+    }
+
+    #[test]
+    fn charging_and_discharging() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Charging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "30000000"),
+            ],
+        );
+        supply(
+            dir.path(),
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+
+        let battery = battery(dir.path(), &["BAT0", "BAT1"]).unwrap();
+        let total = battery.total().unwrap();
+        assert_eq!(total.state, "+");
+        assert_eq!(total.rate, Some(30000000));
+    }
+
+    #[test]
+    fn mixed_families_without_voltage() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("charge_now", "1000000"),
+                ("charge_full", "2000000"),
+                ("current_now", "1000000"),
+            ],
+        );
+        supply(
+            dir.path(),
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("capacity", "25"),
+                ("status", "Discharging"),
+                ("energy_now", "5000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+
+        let battery = battery(dir.path(), &["BAT0", "BAT1"]).unwrap();
+
+        // only BAT1 can be converted to energy
+        let total = battery.total().unwrap();
+        assert_eq!(total.capacity, 25);
+        assert_eq!(settle(&battery), Some(Estimate::Minutes(30)));
+    }
+
+    #[test]
+    fn detect_all() {
+        let dir = tempfile::tempdir().unwrap();
+        let pack = [
+            COMMON,
+            &[
+                ("status", "Discharging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+            ],
+        ]
+        .concat();
+
+        supply(dir.path(), "BAT1", &pack);
+        supply(
+            dir.path(),
+            "BAT0",
+            &[&pack[..], &[("scope", "System")]].concat(),
+        );
+        supply(
+            dir.path(),
+            "hidpp_battery_0",
+            &[&pack[..], &[("scope", "Device")]].concat(),
+        );
+        supply(dir.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+
+        let paths = pack::detect(dir.path());
+        assert_eq!(paths, [dir.path().join("BAT0"), dir.path().join("BAT1")]);
+        assert_eq!(battery(dir.path(), &["all"]).unwrap().packs.len(), 2);
+    }
+
+    #[test]
+    fn health_and_thresholds() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                COMMON,
+                &[
+                    ("status", "Discharging"),
+                    ("energy_now", "10000000"),
+                    ("energy_full", "45000000"),
+                    ("energy_full_design", "50000000"),
+                    ("cycle_count", "123"),
+                    ("charge_control_start_threshold", "40"),
+                    ("charge_control_end_threshold", "80"),
+                ],
+            ]
+            .concat(),
+        );
+        supply(
+            dir.path(),
+            "BAT1",
+            &[
+                COMMON,
+                &[
+                    ("status", "Unknown"),
+                    ("energy_now", "10000000"),
+                    ("energy_full", "25000000"),
+                    ("energy_full_design", "30000000"),
+                    ("cycle_count", "0"),
+                ],
+            ]
+            .concat(),
+        );
+
+        let battery = battery(dir.path(), &["all"]).unwrap();
+
+        // 70 Wh out of 80 Wh
+        assert_eq!(battery.details(), (Some(87), Some(123), Some((40, 80))));
+    }
+}
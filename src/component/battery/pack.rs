@@ -0,0 +1,248 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use super::State;
+use crate::sysfs::SysfsAttr;
+
+const CAPACITY: &str = "capacity";
+const STATE: &str = "status";
+const VOLTAGE: &str = "voltage_now";
+const CURRENT: &str = "current_now";
+const CYCLE_COUNT: &str = "cycle_count";
+const START_THRESHOLD: &str = "charge_control_start_threshold";
+const CHARGE_THRESHOLD: &str = "charge_control_end_threshold";
+
+/// the family of attributes that the battery reports its charge with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Family {
+    /// charge_now, charge_full (µAh) and current_now (µA)
+    Charge,
+
+    /// energy_now, energy_full (µWh) and power_now (µW)
+    Energy,
+}
+
+impl Family {
+    pub fn detect(bat_path: &Path) -> Option<Self> {
+        if bat_path.join("energy_now").exists() {
+            Some(Self::Energy)
+        } else if bat_path.join("charge_now").exists() {
+            Some(Self::Charge)
+        } else {
+            None
+        }
+    }
+
+    // (now, full, full design, rate)
+    fn files(self) -> (&'static str, &'static str, &'static str, &'static str) {
+        match self {
+            Self::Charge => (
+                "charge_now",
+                "charge_full",
+                "charge_full_design",
+                "current_now",
+            ),
+            Self::Energy => (
+                "energy_now",
+                "energy_full",
+                "energy_full_design",
+                "power_now",
+            ),
+        }
+    }
+
+    /// the unit of the charge/energy, after dividing by 10^6
+    pub fn unit(self) -> &'static str {
+        match self {
+            Self::Charge => "Ah",
+            Self::Energy => "Wh",
+        }
+    }
+}
+
+/// a single battery pack, e.g. BAT0
+pub struct Pack {
+    path: PathBuf,
+    family: Family,
+    capacity: SysfsAttr,
+    status: SysfsAttr,
+    now: SysfsAttr,
+    full: SysfsAttr,
+    full_design: Option<SysfsAttr>,
+    rate: Option<SysfsAttr>,
+    /// the current of an energy pack that doesn't report power_now, which is multiplied by the
+    /// voltage instead
+    current: Option<SysfsAttr>,
+    voltage: Option<SysfsAttr>,
+    cycle_count: Option<SysfsAttr>,
+    start_threshold: Option<SysfsAttr>,
+    charge_threshold: Option<SysfsAttr>,
+}
+
+/// a snapshot of a pack, in the units of its family
+#[derive(Debug, Clone, Copy)]
+pub struct Reading {
+    pub state: State,
+    pub capacity: u8,
+    pub now: u64,
+    pub full: u64,
+    /// the charge/energy at which charging stops, considering the charge threshold
+    pub target: u64,
+    pub rate: Option<u64>,
+}
+
+/// the health of a pack, and its configured charge thresholds
+#[derive(Debug, Clone, Copy)]
+pub struct Details {
+    /// the full charge/energy when the pack was new
+    pub full_design: Option<u64>,
+    pub cycle_count: Option<u64>,
+    pub start_threshold: Option<u8>,
+    pub end_threshold: Option<u8>,
+}
+
+impl Pack {
+    pub fn open(path: &Path) -> io::Result<Self> {
+        let open = |file| SysfsAttr::open(path.join(file));
+
+        let family = Family::detect(path).ok_or(io::ErrorKind::NotFound)?;
+        let (now, full, full_design, rate) = family.files();
+        let rate = open(rate).ok();
+        let current = match family {
+            Family::Energy if rate.is_none() => open(CURRENT).ok(),
+            _ => None,
+        };
+
+        Ok(Self {
+            path: path.to_path_buf(),
+            family,
+            capacity: open(CAPACITY)?,
+            status: open(STATE)?,
+            now: open(now)?,
+            full: open(full)?,
+            full_design: open(full_design).ok(),
+            rate,
+            current,
+            voltage: open(VOLTAGE).ok(),
+            cycle_count: open(CYCLE_COUNT).ok(),
+            start_threshold: open(START_THRESHOLD).ok(),
+            charge_threshold: open(CHARGE_THRESHOLD).ok(),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn family(&self) -> Family {
+        self.family
+    }
+
+    pub fn read(&self) -> io::Result<Reading> {
+        let state = self.status.read_with(|status| match status {
+            b"Charging" => "+",
+            b"Discharging" => "-",
+            b"Not charging" | b"Full" => "o",
+            _ => "?",
+        })?;
+
+        let full = self.full.read()?;
+        let threshold: u64 = match self.charge_threshold.as_ref() {
+            Some(threshold) => threshold.read()?,
+            None => 100,
+        };
+
+        Ok(Reading {
+            state,
+            capacity: self.capacity.read()?,
+            now: self.now.read()?,
+            full,
+            target: full * threshold / 100,
+            rate: self.rate()?,
+        })
+    }
+
+    /// µA or µW, depending on the family. some drivers report a negative rate while discharging,
+    /// the direction comes from the status instead.
+    fn rate(&self) -> io::Result<Option<u64>> {
+        if let Some(rate) = self.rate.as_ref() {
+            return Ok(Some(rate.read::<i64>()?.unsigned_abs()));
+        }
+
+        let (Some(current), Some(voltage)) = (self.current.as_ref(), self.voltage.as_ref()) else {
+            return Ok(None);
+        };
+
+        // µA * µV = 10^-12 W
+        let current = current.read::<i64>()?.unsigned_abs();
+        Ok(Some(current * voltage.read::<u64>()? / 1_000_000))
+    }
+
+    /// missing attributes (e.g. cycle_count on some laptops) are left empty
+    pub fn details(&self) -> Details {
+        fn read<T: std::str::FromStr>(attr: &Option<SysfsAttr>) -> Option<T> {
+            attr.as_ref()?.read().ok()
+        }
+
+        Details {
+            full_design: read(&self.full_design),
+            // some firmwares report 0 when they don't count cycles
+            cycle_count: read(&self.cycle_count).filter(|&count| count > 0),
+            start_threshold: read(&self.start_threshold),
+            end_threshold: read(&self.charge_threshold),
+        }
+    }
+
+    /// same as `read`, but converted to µWh and µW. fails for a charge pack without a voltage
+    pub fn read_energy(&self) -> io::Result<Reading> {
+        let reading = self.read()?;
+        if self.family == Family::Energy {
+            return Ok(reading);
+        }
+
+        // µAh * µV = 10^-12 Wh. without a voltage the pack can't be compared to the others
+        let voltage: u64 = self
+            .voltage
+            .as_ref()
+            .ok_or(io::ErrorKind::NotFound)?
+            .read()?;
+        let to_energy = |charge: u64| charge * voltage / 1_000_000;
+
+        Ok(Reading {
+            now: to_energy(reading.now),
+            full: to_energy(reading.full),
+            target: to_energy(reading.target),
+            rate: reading.rate.map(to_energy),
+            ..reading
+        })
+    }
+}
+
+/// the paths of the given batteries in `dir`, or of every battery that powers the system when
+/// `names` is `all` (or empty)
+pub fn resolve(dir: &Path, names: &[String]) -> Vec<PathBuf> {
+    if names.is_empty() || names == ["all"] {
+        detect(dir)
+    } else {
+        names.iter().map(|name| dir.join(name)).collect()
+    }
+}
+
+/// every battery in `dir` that powers the system, e.g. BAT0 and BAT1 (but not the battery of a
+/// wireless mouse)
+pub fn detect(dir: &Path) -> Vec<PathBuf> {
+    let mut paths: Vec<_> = dir
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            let read = |file| std::fs::read_to_string(path.join(file)).unwrap_or_default();
+            read("type").trim() == "Battery" && read("scope").trim() != "Device"
+        })
+        .collect();
+
+    paths.sort();
+    paths
+}
@@ -0,0 +1,226 @@
+use std::path::Path;
+use std::{fmt, fs};
+
+use super::pack::{self, Pack};
+use super::{POWER_SUPPLY, combine, state_color};
+use crate::color::Color;
+use crate::component::Fg;
+use crate::component::temperature::{HOT, WARM};
+use crate::sysfs::SysfsAttr;
+
+/// whether the system runs on AC, and the power drawn from the batteries while they discharge
+pub struct Power {
+    mains: Vec<SysfsAttr>,
+    packs: Vec<Pack>,
+    warn: f32,
+    crit: f32,
+}
+
+impl Power {
+    /// `batteries` are the names of the batteries to measure (or `all`, or empty). the draw is shown as warm
+    /// from `warn` watts, and as hot from `crit` watts.
+    pub fn new(batteries: &[String], warn: f32, crit: f32) -> Self {
+        Self::in_dir(Path::new(POWER_SUPPLY), batteries, warn, crit)
+    }
+
+    fn in_dir(dir: &Path, batteries: &[String], warn: f32, crit: f32) -> Self {
+        let mains = dir
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                fs::read_to_string(path.join("type")).is_ok_and(|kind| kind.trim() == "Mains")
+            })
+            .filter_map(|path| SysfsAttr::open(path.join("online")).ok())
+            .collect();
+
+        let packs = pack::resolve(dir, batteries)
+            .iter()
+            .filter_map(|path| Pack::open(path).ok())
+            .collect();
+
+        Self {
+            mains,
+            packs,
+            warn,
+            crit,
+        }
+    }
+
+    fn color(&self, watts: f32) -> Color {
+        if watts < self.warn {
+            crate::FG
+        } else if watts < self.crit {
+            WARM
+        } else {
+            HOT
+        }
+    }
+}
+
+impl fmt::Display for Power {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.mains.is_empty() {
+            let online = self
+                .mains
+                .iter()
+                .any(|online| online.read::<u8>().is_ok_and(|online| online == 1));
+
+            let (state, text) = if online { ("+", "AC") } else { ("-", "BAT") };
+            write!(f, "{}{:3}{} ", Fg(state_color(state)), text, Fg(crate::FG))?;
+        }
+
+        let readings: Vec<_> = self
+            .packs
+            .iter()
+            .filter_map(|pack| pack.read_energy().ok())
+            .collect();
+        let state = readings.iter().map(|reading| reading.state).reduce(combine);
+
+        // µW, power_now or current_now * voltage_now. the charge rate isn't a draw, and a pack
+        // that charges from another one doesn't add to it
+        let micro_watts = readings
+            .iter()
+            .filter(|reading| reading.state == "-")
+            .filter_map(|reading| reading.rate)
+            .reduce(|a, b| a + b);
+
+        let (Some("-"), Some(micro_watts)) = (state, micro_watts) else {
+            return write!(f, "{}{:>5}{}", Fg(Color::DIM), "-", Fg(crate::FG));
+        };
+
+        let watts = micro_watts as f32 / 1_000_000.;
+        write!(f, "{}", Fg(self.color(watts)))?;
+        if watts < 99.95 {
+            write!(f, "{:4.1}W", watts)?;
+        } else {
+            write!(f, "{:4.0}W", watts)?;
+        }
+
+        write!(f, "{}", Fg(crate::FG))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::super::tests::supply;
+    use super::*;
+    use crate::fixture;
+
+    fn power(root: &Path) -> String {
+        fixture::plain(&Power::in_dir(root, &["all".to_string()], 15., 30.).to_string())
+    }
+
+    #[test]
+    fn sums_packs() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(dir.path(), "AC", &[("type", "Mains"), ("online", "0")]);
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "7500000"),
+            ],
+        );
+        supply(
+            dir.path(),
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("charge_now", "1000000"),
+                ("charge_full", "2000000"),
+                ("current_now", "500000"),
+                ("voltage_now", "10000000"),
+            ],
+        );
+
+        // 7.5 W + 0.5 A * 10 V
+        assert_eq!(power(dir.path()), "BAT 12.5W");
+    }
+
+    #[test]
+    fn charging() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(dir.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Charging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "25000000"),
+            ],
+        );
+        assert_eq!(power(dir.path()), "AC      -");
+
+        // one pack charges the other
+        supply(
+            dir.path(),
+            "BAT1",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+                ("power_now", "10000000"),
+            ],
+        );
+        fs::write(dir.path().join("AC/online"), "0\n").unwrap();
+        assert_eq!(power(dir.path()), "BAT     -");
+    }
+
+    #[test]
+    fn current_without_power() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Discharging"),
+                ("energy_now", "10000000"),
+                ("energy_full", "20000000"),
+                ("current_now", "2000000"),
+                ("voltage_now", "12000000"),
+            ],
+        );
+
+        assert_eq!(power(dir.path()), "24.0W");
+    }
+
+    #[test]
+    fn unavailable() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(dir.path(), "AC", &[("type", "Mains"), ("online", "1")]);
+        assert_eq!(power(dir.path()), "AC      -");
+
+        supply(
+            dir.path(),
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("capacity", "50"),
+                ("status", "Full"),
+                ("energy_now", "20000000"),
+                ("energy_full", "20000000"),
+            ],
+        );
+        assert_eq!(power(dir.path()), "AC      -");
+    }
+}
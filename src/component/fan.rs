@@ -0,0 +1,219 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::component::hwmon::{self, HWMON, SelectError};
+use crate::component::{Bg, DisplayExt as _, Fg, USAGE_BG, usage_bar};
+use crate::sysfs::SysfsAttr;
+
+const LOUDEST: &str = "loudest";
+
+/// fan speeds of a hwmon chip, selected the same way as temperatures: `NAME[:FANS]`, where FANS
+/// is either a comma separated list of fanN_label values or `max` for the fastest fan of the chip.
+/// without FANS, the first fan of the chip is shown. `loudest` is the fastest fan across all of the chips.
+pub struct Fan {
+    fans: Vec<FanInput>,
+    max: bool,
+    bar: bool,
+}
+
+struct FanInput {
+    /// RPM
+    input: SysfsAttr,
+    /// duty cycle, 0-255
+    pwm: Option<SysfsAttr>,
+}
+
+impl FanInput {
+    fn open(chip: &Path, index: u32) -> Result<Self, FanError> {
+        let input = SysfsAttr::open(chip.join(format!("fan{index}_input")))?;
+        // pwmN usually drives fanN
+        let pwm = SysfsAttr::open(chip.join(format!("pwm{index}"))).ok();
+        Ok(Self { input, pwm })
+    }
+}
+
+impl Fan {
+    /// `bar` also shows the duty cycle (from pwmN) as a bar
+    pub fn create(spec: &str, bar: bool) -> Result<Self, FanError> {
+        Self::open(Path::new(HWMON), spec, bar)
+    }
+
+    fn open(dir: &Path, spec: &str, bar: bool) -> Result<Self, FanError> {
+        let chips = hwmon::chips(dir).ok_or(FanError::NoHwmonDirectory)?;
+
+        if spec == LOUDEST {
+            let fans = chips
+                .iter()
+                .flat_map(|(_, chip)| {
+                    hwmon::inputs(chip, "fan")
+                        .into_iter()
+                        .map(|index| FanInput::open(chip, index))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+
+            if fans.is_empty() {
+                return Err(FanError::NoFans);
+            }
+
+            return Ok(Self {
+                fans,
+                max: true,
+                bar,
+            });
+        }
+
+        let (name, selection) = match spec.split_once(':') {
+            Some((name, selection)) => (name, Some(selection)),
+            None => (spec, None),
+        };
+
+        let (_, chip) = chips
+            .iter()
+            .find(|(chip_name, _)| chip_name == name)
+            .ok_or_else(|| FanError::NoSuchChip {
+                name: name.to_string(),
+                available: hwmon::names(&chips),
+            })?;
+
+        let selection = hwmon::select(chip, "fan", selection).map_err(|err| match err {
+            SelectError::Empty => FanError::NoFans,
+            SelectError::NoSuchLabel { label, available } => {
+                FanError::NoSuchFan { label, available }
+            }
+        })?;
+
+        let fans = selection
+            .indices
+            .into_iter()
+            .map(|index| FanInput::open(chip, index))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            fans,
+            max: selection.max,
+            bar,
+        })
+    }
+
+    /// (RPM, duty cycle) of the fans to show
+    fn readings(&self) -> impl Iterator<Item = (u32, Option<u8>)> {
+        let readings = self.fans.iter().filter_map(|fan| {
+            let rpm = fan.input.read().ok()?;
+            let pwm = fan.pwm.as_ref().and_then(|pwm| pwm.read().ok());
+            Some((rpm, pwm))
+        });
+
+        let (all, max) = if self.max {
+            (None, readings.max_by_key(|(rpm, _)| *rpm))
+        } else {
+            (Some(readings), None)
+        };
+
+        all.into_iter().flatten().chain(max)
+    }
+}
+
+impl fmt::Display for Fan {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (rpm, pwm)) in self.readings().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+
+            write!(f, "{:4}rpm", rpm)?;
+
+            if self.bar
+                && let Some(pwm) = pwm
+            {
+                let bar = Bg(USAGE_BG).chain(usage_bar(pwm as f32 / 255.));
+                write!(f, " {}{}{}", bar, Bg(crate::BG), Fg(crate::FG))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum FanError {
+    #[error("no hwmon chip named `{name}`, available: {available}")]
+    NoSuchChip { name: String, available: String },
+
+    #[error("no fans found")]
+    NoFans,
+
+    #[error("no fan labeled `{label}`, available: {available}")]
+    NoSuchFan { label: String, available: String },
+
+    #[error("/sys/class/hwmon directory does not exist")]
+    NoHwmonDirectory,
+
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    fn sample_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        fixture::hwmon_chip(dir.path(), "hwmon0", "thinkpad", "fan", &[(None, 2100)]);
+        fixture::attrs(&dir.path().join("hwmon0"), &[("pwm1", 128)]);
+        fixture::hwmon_chip(
+            dir.path(),
+            "hwmon1",
+            "dell_smm",
+            "fan",
+            &[(Some("Processor Fan"), 3200), (Some("Video Fan"), 1500)],
+        );
+        dir
+    }
+
+    fn rpms(fan: &Fan) -> Vec<u32> {
+        fan.readings().map(|(rpm, _)| rpm).collect()
+    }
+
+    #[test]
+    fn selects_by_label() {
+        let dir = sample_tree();
+
+        let fan = Fan::open(dir.path(), "dell_smm", false).unwrap();
+        assert_eq!(rpms(&fan), [3200]);
+
+        let fan = Fan::open(dir.path(), "dell_smm:Processor Fan,Video Fan", false).unwrap();
+        assert_eq!(rpms(&fan), [3200, 1500]);
+
+        let fan = Fan::open(dir.path(), "dell_smm:Video Fan", false).unwrap();
+        assert_eq!(rpms(&fan), [1500]);
+
+        let fan = Fan::open(dir.path(), "dell_smm:max", false).unwrap();
+        assert_eq!(rpms(&fan), [3200]);
+
+        assert!(matches!(
+            Fan::open(dir.path(), "dell_smm:CPU", false),
+            Err(FanError::NoSuchFan { .. })
+        ));
+        assert!(matches!(
+            Fan::open(dir.path(), "nct6775", false),
+            Err(FanError::NoSuchChip { .. })
+        ));
+    }
+
+    #[test]
+    fn loudest_across_chips() {
+        let dir = sample_tree();
+
+        let fan = Fan::open(dir.path(), "loudest", false).unwrap();
+        assert_eq!(rpms(&fan), [3200]);
+    }
+
+    #[test]
+    fn duty_cycle() {
+        let dir = sample_tree();
+
+        let fan = Fan::open(dir.path(), "thinkpad", true).unwrap();
+        assert_eq!(fan.readings().collect::<Vec<_>>(), [(2100, Some(128))]);
+    }
+}
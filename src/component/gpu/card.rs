@@ -0,0 +1,138 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::fdinfo;
+
+/// matches every card with usage data
+pub const AUTO: &str = "auto";
+
+/// a DRM card, e.g. card0
+#[derive(Debug)]
+pub struct Card {
+    pub name: String,
+    /// the card's `device/` directory
+    pub device: PathBuf,
+    /// PCI vendor id, e.g. 1002
+    pub vendor: Option<u16>,
+    /// PCI device id, e.g. 73df
+    pub device_id: Option<u16>,
+    /// the kernel driver, e.g. amdgpu
+    pub driver: Option<String>,
+    /// PCI address, e.g. 0000:00:02.0
+    pub pdev: Option<String>,
+}
+
+impl Card {
+    fn open(name: String, path: &Path) -> Self {
+        let device = path.join("device");
+        let driver = fs::read_link(device.join("driver"))
+            .ok()
+            .and_then(|driver| Some(driver.file_name()?.to_str()?.to_string()));
+
+        // device links to the PCI device
+        let pdev = fs::read_link(&device)
+            .ok()
+            .and_then(|pdev| Some(pdev.file_name()?.to_str()?.to_string()));
+
+        Self {
+            name,
+            pdev,
+            vendor: read_id(&device.join("vendor")),
+            device_id: read_id(&device.join("device")),
+            driver,
+            device,
+        }
+    }
+
+    pub fn has_busy(&self) -> bool {
+        self.device.join("gpu_busy_percent").exists()
+    }
+
+    /// whether the usage can be computed from the fdinfo of the processes instead
+    pub fn has_fdinfo(&self) -> bool {
+        self.pdev.is_some()
+            && self
+                .driver
+                .as_deref()
+                .is_some_and(|driver| fdinfo::DRIVERS.contains(&driver))
+    }
+
+    pub fn has_usage(&self) -> bool {
+        self.has_busy() || self.has_fdinfo()
+    }
+
+    /// whether the card is selected by `spec`: its name (card0), PCI ids (VENDOR[:DEVICE] in hex, e.g. 1002:73df),
+    /// or its driver name (amdgpu)
+    pub fn matches(&self, spec: &str) -> bool {
+        if spec == AUTO {
+            return self.has_usage();
+        }
+
+        if spec == self.name || Some(spec) == self.driver.as_deref() {
+            return true;
+        }
+
+        let (vendor, device) = match spec.split_once(':') {
+            Some((vendor, device)) => (vendor, Some(device)),
+            None => (spec, None),
+        };
+
+        let Some(vendor) = parse_id(vendor) else {
+            return false;
+        };
+
+        match device {
+            Some(device) => {
+                Some(vendor) == self.vendor
+                    && parse_id(device).is_some_and(|device| Some(device) == self.device_id)
+            }
+            None => Some(vendor) == self.vendor,
+        }
+    }
+}
+
+impl fmt::Display for Card {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} (", self.name)?;
+        if let Some(driver) = &self.driver {
+            write!(f, "{driver} ")?;
+        }
+        match (self.vendor, self.device_id) {
+            (Some(vendor), Some(device)) => write!(f, "{vendor:04x}:{device:04x})"),
+            _ => write!(f, "unknown)"),
+        }
+    }
+}
+
+/// PCI ids are 4 hex digits, with or without 0x
+fn parse_id(id: &str) -> Option<u16> {
+    let id = id.trim();
+    let id = id.strip_prefix("0x").unwrap_or(id);
+    if id.len() != 4 {
+        return None;
+    }
+    u16::from_str_radix(id, 16).ok()
+}
+
+fn read_id(path: &Path) -> Option<u16> {
+    parse_id(&fs::read_to_string(path).ok()?)
+}
+
+/// the cards in `dir` (e.g. /sys/class/drm) sorted by number, without their connectors (card0-DP-1)
+pub fn cards(dir: &Path) -> Vec<Card> {
+    let mut cards: Vec<_> = dir
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            let number: u32 = name.strip_prefix("card")?.parse().ok()?;
+            Some((number, Card::open(name, &entry.path())))
+        })
+        .collect();
+
+    cards.sort_by_key(|(number, _)| *number);
+    cards.into_iter().map(|(_, card)| card).collect()
+}
@@ -0,0 +1,547 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+pub const PROC: &str = "/proc";
+
+/// how often every fd of every process is checked for new DRM files, which is much more expensive
+/// than reading the fdinfo of the known ones
+const RESCAN: Duration = Duration::from_secs(10);
+
+/// drivers whose fdinfo reports engine usage, for cards without gpu_busy_percent
+pub const DRIVERS: &[&str] = &["i915", "xe"];
+
+/// the time an engine class was busy for a client
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EngineTime {
+    /// `drm-engine-NAME: N ns` (i915, amdgpu)
+    Nanos(u64),
+    /// `drm-cycles-NAME: N` and `drm-total-cycles-NAME: N` (xe), where total keeps running while idle
+    Cycles { busy: u64, total: u64 },
+}
+
+/// a DRM client (an open device file), from /proc/PID/fdinfo/FD
+#[derive(Debug, Default, PartialEq)]
+pub struct Client {
+    pub pid: u32,
+    /// PCI address of the device, if the driver reports it
+    pub pdev: String,
+    /// unique per client of a device, even when the file is shared with dup or fork
+    pub id: u64,
+    pub engines: HashMap<String, EngineTime>,
+    /// the number of engines of a class, if more than 1
+    pub capacity: HashMap<String, u32>,
+    /// bytes of memory in all regions
+    pub memory: u64,
+}
+
+impl Client {
+    /// None if `text` isn't the fdinfo of a DRM file
+    fn parse(text: &str) -> Option<Self> {
+        let mut client = Self::default();
+        let mut id = None;
+        let mut resident = 0;
+        let mut cycles = HashMap::new();
+        let mut total_cycles = HashMap::new();
+
+        for line in text.lines() {
+            let Some((key, value)) = line.split_once(':') else {
+                continue;
+            };
+            let value = value.trim();
+
+            if key == "drm-pdev" {
+                client.pdev = value.to_string();
+            } else if key == "drm-client-id" {
+                id = value.parse().ok();
+            } else if key.starts_with("drm-memory-") {
+                client.memory += parse_memory(value).unwrap_or(0);
+            } else if key.starts_with("drm-resident-") {
+                resident += parse_memory(value).unwrap_or(0);
+            } else if let Some(engine) = key.strip_prefix("drm-engine-capacity-") {
+                if let Ok(capacity) = value.parse() {
+                    client.capacity.insert(engine.to_string(), capacity);
+                }
+            } else if let Some(engine) = key.strip_prefix("drm-engine-") {
+                if let Some(Ok(ns)) = value.strip_suffix("ns").map(|ns| ns.trim().parse()) {
+                    client
+                        .engines
+                        .insert(engine.to_string(), EngineTime::Nanos(ns));
+                }
+            } else if let Some(engine) = key.strip_prefix("drm-total-cycles-") {
+                if let Ok(total) = value.parse::<u64>() {
+                    total_cycles.insert(engine, total);
+                }
+            } else if let Some(engine) = key.strip_prefix("drm-cycles-")
+                && let Ok(busy) = value.parse::<u64>()
+            {
+                cycles.insert(engine, busy);
+            }
+        }
+
+        for (engine, busy) in cycles {
+            if let Some(&total) = total_cycles.get(engine) {
+                client
+                    .engines
+                    .insert(engine.to_string(), EngineTime::Cycles { busy, total });
+            }
+        }
+
+        // drm-memory-* is the older name of drm-resident-*
+        if client.memory == 0 {
+            client.memory = resident;
+        }
+
+        client.id = id?;
+        Some(client)
+    }
+}
+
+/// e.g. `1024 KiB`
+fn parse_memory(value: &str) -> Option<u64> {
+    let (number, unit) = value.split_once(' ').unwrap_or((value, ""));
+    let multiplier = match unit.trim() {
+        "" => 1,
+        "KiB" => 1 << 10,
+        "MiB" => 1 << 20,
+        _ => return None,
+    };
+    Some(number.parse::<u64>().ok()? * multiplier)
+}
+
+/// the busy share (0-1) of an engine between two samples that are `elapsed` ns apart
+pub fn share(last: EngineTime, now: EngineTime, elapsed: f64) -> Option<f64> {
+    match (last, now) {
+        (EngineTime::Nanos(last), EngineTime::Nanos(now)) if elapsed > 0. => {
+            Some(now.saturating_sub(last) as f64 / elapsed)
+        }
+        (
+            EngineTime::Cycles {
+                busy: last,
+                total: last_total,
+            },
+            EngineTime::Cycles { busy: now, total },
+        ) if total > last_total => {
+            Some(now.saturating_sub(last) as f64 / (total - last_total) as f64)
+        }
+        _ => None,
+    }
+}
+
+/// the DRM files of every process in `proc`, which are found by a full scan every `RESCAN`, and
+/// read in between. files that are opened in between are picked up by the next scan.
+pub struct Clients {
+    proc: PathBuf,
+    /// pid and fdinfo path
+    files: Vec<(u32, PathBuf)>,
+    scanned: Option<Instant>,
+    /// reused for reading every fdinfo
+    text: String,
+}
+
+impl Clients {
+    pub fn new(proc: PathBuf) -> Self {
+        Self {
+            proc,
+            files: Vec::new(),
+            scanned: None,
+            text: String::new(),
+        }
+    }
+
+    fn scan(&mut self) {
+        self.files.clear();
+
+        let pids = self
+            .proc
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter_map(|entry| {
+                Some((
+                    entry.file_name().to_str()?.parse::<u32>().ok()?,
+                    entry.path(),
+                ))
+            });
+
+        for (pid, dir) in pids {
+            // unreadable for processes of other users, or gone already
+            let Ok(fds) = dir.join("fd").read_dir() else {
+                continue;
+            };
+
+            for fd in fds.flatten() {
+                // only DRM files, without reading the fdinfo of everything else
+                let is_drm =
+                    fs::read_link(fd.path()).is_ok_and(|path| path.starts_with("/dev/dri"));
+                if is_drm {
+                    self.files
+                        .push((pid, dir.join("fdinfo").join(fd.file_name())));
+                }
+            }
+        }
+    }
+
+    /// the DRM clients of the device at PCI address `pdev` (e.g. 0000:00:02.0), or of all
+    /// devices, once per client
+    pub fn read(&mut self, now: Instant, pdev: Option<&str>) -> Vec<Client> {
+        let due = self
+            .scanned
+            .is_none_or(|scanned| now.duration_since(scanned) >= RESCAN);
+        if due {
+            self.scan();
+            self.scanned = Some(now);
+        }
+
+        let mut clients = Vec::new();
+        let text = &mut self.text;
+
+        // files that were closed since the scan, or whose fd now refers to something else
+        self.files.retain(|(pid, fdinfo)| {
+            text.clear();
+            let read = File::open(fdinfo).and_then(|mut file| file.read_to_string(text));
+            let Some(client) = read.ok().and_then(|_| Client::parse(text)) else {
+                return false;
+            };
+
+            if pdev.is_none_or(|pdev| pdev == client.pdev) {
+                clients.push(Client {
+                    pid: *pid,
+                    ..client
+                });
+            }
+            true
+        });
+
+        // a file that's shared with dup or fork is the same client, the first process keeps it
+        clients.sort_by(|a, b| (&a.pdev, a.id, a.pid).cmp(&(&b.pdev, b.id, b.pid)));
+        clients.dedup_by(|b, a| (&a.pdev, a.id) == (&b.pdev, b.id));
+        clients
+    }
+}
+
+/// the engine times of all clients, by device, client id and engine
+type Times = HashMap<(String, u64, String), EngineTime>;
+
+/// the engine usage of a client between two samples
+#[derive(Debug, PartialEq)]
+pub struct ClientUsage {
+    pub pid: u32,
+    pub pdev: String,
+    /// bytes of memory in all regions
+    pub memory: u64,
+    /// the busy share (0-1) of every engine class, out of all engines of the class
+    pub engines: HashMap<String, f64>,
+}
+
+/// samples the engine times of the DRM clients of a device, or of all devices
+pub struct Sampler {
+    clients: Clients,
+    pdev: Option<String>,
+    last: Option<(Instant, Times)>,
+    /// the map of the sample before `last`, reused for the next one
+    spare: Times,
+}
+
+impl Sampler {
+    pub fn new(proc: PathBuf, pdev: Option<String>) -> Self {
+        Self {
+            clients: Clients::new(proc),
+            pdev,
+            last: None,
+            spare: Times::new(),
+        }
+    }
+
+    /// when the previous sample was taken
+    pub fn sampled(&self) -> Option<Instant> {
+        self.last.as_ref().map(|(then, _)| *then)
+    }
+
+    /// the usage of every client since the previous sample, None on the first one
+    pub fn sample(&mut self, now: Instant) -> Option<Vec<ClientUsage>> {
+        let clients = self.clients.read(now, self.pdev.as_deref());
+
+        let mut capacity: HashMap<(&str, &str), u32> = HashMap::new();
+        let mut times = std::mem::take(&mut self.spare);
+        times.clear();
+        for client in &clients {
+            for (engine, &count) in &client.capacity {
+                capacity.insert((&client.pdev, engine), count);
+            }
+            for (engine, &time) in &client.engines {
+                times.insert((client.pdev.clone(), client.id, engine.clone()), time);
+            }
+        }
+
+        let (then, last_times) = self.last.replace((now, times))?;
+        let elapsed = now.duration_since(then).as_nanos() as f64;
+
+        let usage = clients
+            .iter()
+            .map(|client| {
+                let engines = client
+                    .engines
+                    .iter()
+                    .filter_map(|(engine, &time)| {
+                        // clients that just appeared have no reference point
+                        let key = (client.pdev.clone(), client.id, engine.clone());
+                        let share = share(*last_times.get(&key)?, time, elapsed)?;
+                        let count = capacity
+                            .get(&(client.pdev.as_str(), engine.as_str()))
+                            .copied()
+                            .unwrap_or(1)
+                            .max(1);
+                        Some((engine.clone(), share / count as f64))
+                    })
+                    .collect();
+
+                ClientUsage {
+                    pid: client.pid,
+                    pdev: client.pdev.clone(),
+                    memory: client.memory,
+                    engines,
+                }
+            })
+            .collect();
+
+        self.spare = last_times;
+        Some(usage)
+    }
+}
+
+/// engine usage of a device, summed over all clients
+pub struct EngineUsage {
+    sampler: Sampler,
+}
+
+impl EngineUsage {
+    pub fn new(proc: PathBuf, pdev: String) -> Self {
+        Self {
+            sampler: Sampler::new(proc, Some(pdev)),
+        }
+    }
+
+    /// the busy share (0-1) of the busiest engine class since the previous sample, None on the first one
+    pub fn sample(&mut self, now: Instant) -> Option<f32> {
+        let usage = self.sampler.sample(now)?;
+
+        let mut busy: HashMap<&str, f64> = HashMap::new();
+        for client in &usage {
+            for (engine, share) in &client.engines {
+                *busy.entry(engine).or_default() += share;
+            }
+        }
+
+        let busiest = busy.into_values().fold(0., f64::max);
+        Some(busiest.min(1.) as f32)
+    }
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::fixture;
+
+    const INTEL: &str = "0000:00:02.0";
+
+    pub fn fd(proc: &Path, pid: u32, fd: u32, target: &str, fdinfo: &str) {
+        let dir = proc.join(pid.to_string());
+        fixture::symlink(target, &dir.join("fd").join(fd.to_string()));
+        fs::create_dir_all(dir.join("fdinfo")).unwrap();
+        fs::write(dir.join("fdinfo").join(fd.to_string()), fdinfo).unwrap();
+    }
+
+    pub fn i915(client: u64, render: u64, video: u64) -> String {
+        format!(
+            "pos:\t0\nflags:\t02100002\nmnt_id:\t26\nino:\t1095\n\
+             drm-driver:\ti915\ndrm-client-id:\t{client}\ndrm-pdev:\t{INTEL}\n\
+             drm-engine-render:\t{render} ns\ndrm-engine-copy:\t0 ns\n\
+             drm-engine-video:\t{video} ns\ndrm-engine-capacity-video:\t2\n"
+        )
+    }
+
+    fn xe(client: u64, busy: u64, total: u64) -> String {
+        format!(
+            "drm-driver:\txe\ndrm-client-id:\t{client}\ndrm-pdev:\t{INTEL}\n\
+             drm-cycles-rcs:\t{busy}\ndrm-total-cycles-rcs:\t{total}\n"
+        )
+    }
+
+    /// a /proc with a browser (two fds for the same client), a video player, a process on another card
+    /// and a process without DRM files
+    fn sample_tree(browser: (u64, u64), player: (u64, u64)) -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let proc = dir.path();
+        fd(proc, 100, 3, "/dev/null", "pos:\t0\n");
+        fd(
+            proc,
+            100,
+            7,
+            "/dev/dri/renderD128",
+            &i915(1, browser.0, browser.1),
+        );
+        fd(
+            proc,
+            101,
+            7,
+            "/dev/dri/renderD128",
+            &i915(1, browser.0, browser.1),
+        );
+        fd(
+            proc,
+            200,
+            4,
+            "/dev/dri/renderD128",
+            &i915(2, player.0, player.1),
+        );
+        fd(
+            proc,
+            300,
+            5,
+            "/dev/dri/renderD129",
+            "drm-client-id:\t3\ndrm-pdev:\t0000:03:00.0\ndrm-engine-gfx:\t999 ns\n",
+        );
+        fs::create_dir_all(proc.join("self")).unwrap();
+        fs::create_dir_all(proc.join("400")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn parse_clients() {
+        let dir = sample_tree((10, 0), (5, 40));
+        let clients = |pdev| Clients::new(dir.path().to_path_buf()).read(Instant::now(), pdev);
+        let found = clients(Some(INTEL));
+
+        assert_eq!(found.len(), 2);
+        assert_eq!((found[0].pid, found[0].id), (100, 1));
+        assert_eq!(found[1].pid, 200);
+        assert_eq!(found[1].engines["render"], EngineTime::Nanos(5));
+        assert_eq!(found[1].engines["video"], EngineTime::Nanos(40));
+        assert_eq!(found[1].engines["copy"], EngineTime::Nanos(0));
+        assert_eq!(found[1].capacity["video"], 2);
+
+        assert!(clients(Some("0000:00:03.0")).is_empty());
+        assert_eq!(clients(None).len(), 3);
+    }
+
+    #[test]
+    fn memory() {
+        let text = "drm-client-id:\t1\ndrm-memory-system:\t4 KiB\ndrm-memory-local:\t2 MiB\n";
+        let client = Client::parse(text).unwrap();
+        assert_eq!(client.memory, 4096 + 2 * 1024 * 1024);
+
+        let text = "drm-client-id:\t1\ndrm-total-system0:\t8 MiB\ndrm-resident-system0:\t12 KiB\n";
+        let client = Client::parse(text).unwrap();
+        assert_eq!(client.memory, 12 * 1024);
+    }
+
+    #[test]
+    fn busiest_engine() {
+        let start = Instant::now();
+        let dir = sample_tree((0, 0), (0, 0));
+        let mut usage = EngineUsage::new(dir.path().to_path_buf(), INTEL.to_string());
+        assert_eq!(usage.sample(start), None);
+
+        // render: 300ms + 100ms of 1s, video: 1s of 2 engines for 1s
+        let dir2 = sample_tree((300_000_000, 0), (100_000_000, 1_000_000_000));
+        usage.sampler.clients = Clients::new(dir2.path().to_path_buf());
+        let busy = usage.sample(start + Duration::from_secs(1)).unwrap();
+        assert!((busy - 0.5).abs() < 1e-6, "{busy}");
+
+        // the video player exited, so only the browser is left
+        let dir3 = tempfile::tempdir().unwrap();
+        fd(
+            dir3.path(),
+            100,
+            7,
+            "/dev/dri/renderD128",
+            &i915(1, 1_300_000_000, 0),
+        );
+        usage.sampler.clients = Clients::new(dir3.path().to_path_buf());
+        let busy = usage.sample(start + Duration::from_secs(2)).unwrap();
+        assert_eq!(busy, 1.);
+    }
+
+    #[test]
+    fn client_usage() {
+        let start = Instant::now();
+        let dir = sample_tree((0, 0), (0, 0));
+        let mut sampler = Sampler::new(dir.path().to_path_buf(), None);
+        assert_eq!(sampler.sample(start), None);
+        assert_eq!(sampler.sampled(), Some(start));
+
+        let dir2 = sample_tree((500_000_000, 0), (0, 1_000_000_000));
+        sampler.clients = Clients::new(dir2.path().to_path_buf());
+        let usage = sampler.sample(start + Duration::from_secs(1)).unwrap();
+
+        let pids: Vec<_> = usage.iter().map(|client| client.pid).collect();
+        assert_eq!(pids, [100, 200, 300]);
+        assert_eq!(usage[0].engines["render"], 0.5);
+        // 1 of the 2 video engines
+        assert_eq!(usage[1].engines["video"], 0.5);
+        assert_eq!(usage[1].engines["render"], 0.);
+        // idle on the other card
+        assert_eq!(usage[2].pdev, "0000:03:00.0");
+        assert_eq!(usage[2].engines["gfx"], 0.);
+    }
+
+    #[test]
+    fn rescan() {
+        let start = Instant::now();
+        let dir = tempfile::tempdir().unwrap();
+        let proc = dir.path();
+        fd(proc, 100, 7, "/dev/dri/renderD128", &i915(1, 0, 0));
+
+        let mut clients = Clients::new(proc.to_path_buf());
+        let pids = |clients: &mut Clients, now| -> Vec<u32> {
+            clients
+                .read(now, None)
+                .iter()
+                .map(|client| client.pid)
+                .collect()
+        };
+        assert_eq!(pids(&mut clients, start), [100]);
+
+        // only found by the next scan
+        fd(proc, 200, 4, "/dev/dri/renderD128", &i915(2, 0, 0));
+        assert_eq!(pids(&mut clients, start + Duration::from_secs(1)), [100]);
+
+        // closed files are dropped right away
+        fs::remove_file(proc.join("100/fdinfo/7")).unwrap();
+        assert!(pids(&mut clients, start + Duration::from_secs(2)).is_empty());
+
+        assert_eq!(pids(&mut clients, start + RESCAN), [200]);
+    }
+
+    #[test]
+    fn xe_cycles() {
+        let start = Instant::now();
+        let dir = tempfile::tempdir().unwrap();
+        fd(
+            dir.path(),
+            100,
+            7,
+            "/dev/dri/renderD128",
+            &xe(1, 1000, 10_000),
+        );
+        let mut usage = EngineUsage::new(dir.path().to_path_buf(), INTEL.to_string());
+        assert_eq!(usage.sample(start), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        fd(
+            dir.path(),
+            100,
+            7,
+            "/dev/dri/renderD128",
+            &xe(1, 1250, 11_000),
+        );
+        usage.sampler.clients = Clients::new(dir.path().to_path_buf());
+        assert_eq!(usage.sample(start + Duration::from_secs(1)), Some(0.25));
+    }
+}
@@ -0,0 +1,425 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::path::Path;
+use std::time::Instant;
+
+use crate::component::hwmon;
+use crate::component::{Bg, DisplayExt as _, Fg, USAGE_BG, usage_bar};
+use crate::sysfs::{self, SysfsAttr};
+
+mod card;
+mod fdinfo;
+mod top;
+
+use card::Card;
+use fdinfo::EngineUsage;
+pub use top::GpuTop;
+
+const DRM: &str = "/sys/class/drm";
+
+/// the optional fields of the GPU component, next to the busy bar
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum GpuField {
+    /// VRAM usage as a second bar
+    Vram,
+    /// temperature in Celsius
+    Temp,
+    /// average power draw in watts
+    Power,
+    /// current shader clock in MHz
+    Clock,
+}
+
+/// one or more cards, selected by name, PCI ids or driver (see [`Card::matches`]), or `auto`
+pub struct Gpus {
+    cards: Vec<Gpu>,
+}
+
+impl Gpus {
+    pub fn create(spec: &str, fields: &[GpuField]) -> Result<Self, NoSuchCard> {
+        Self::open(Path::new(DRM), Path::new(fdinfo::PROC), spec, fields)
+    }
+
+    fn open(dir: &Path, proc: &Path, spec: &str, fields: &[GpuField]) -> Result<Self, NoSuchCard> {
+        let all = card::cards(dir);
+        let cards: Vec<_> = all
+            .iter()
+            .filter(|card| card.matches(spec))
+            .filter_map(|card| Gpu::open(card, proc, fields))
+            .collect();
+
+        if cards.is_empty() {
+            return Err(NoSuchCard {
+                spec: spec.to_string(),
+                available: all
+                    .iter()
+                    .filter(|card| card.has_usage())
+                    .map(Card::to_string)
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            });
+        }
+
+        Ok(Self { cards })
+    }
+}
+
+impl fmt::Display for Gpus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, card) in self.cards.iter().enumerate() {
+            if i > 0 {
+                write!(f, "{} {}", Bg(crate::BG), Bg(USAGE_BG))?;
+            }
+            write!(f, "{card}")?;
+        }
+
+        Ok(())
+    }
+}
+
+/// where the busy percent comes from
+enum Busy {
+    /// gpu_busy_percent
+    Sysfs(SysfsAttr),
+    /// the fdinfo of the processes using the card, for Intel cards
+    Fdinfo(RefCell<EngineUsage>),
+}
+
+struct Gpu {
+    busy: Busy,
+    vram: Option<(SysfsAttr, SysfsAttr)>,
+    temp: Option<SysfsAttr>,
+    power: Option<SysfsAttr>,
+    clock: Option<SysfsAttr>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct Reading {
+    busy: Option<u8>,
+    /// used / total
+    vram: Option<f32>,
+    /// millidegrees Celsius
+    temp: Option<i64>,
+    /// microwatts
+    power: Option<u64>,
+    /// MHz
+    clock: Option<u32>,
+}
+
+impl Gpu {
+    /// None for cards without usage data
+    fn open(card: &Card, proc: &Path, fields: &[GpuField]) -> Option<Self> {
+        let busy = match SysfsAttr::open(card.device.join("gpu_busy_percent")) {
+            Ok(busy) => Busy::Sysfs(busy),
+            Err(_) if card.has_fdinfo() => Busy::Fdinfo(RefCell::new(EngineUsage::new(
+                proc.to_path_buf(),
+                card.pdev.clone()?,
+            ))),
+            Err(_) => return None,
+        };
+
+        Some(Self::in_dir(&card.device, busy, fields))
+    }
+
+    /// fields whose files don't exist for the card (e.g. pp_dpm_sclk on non-AMD cards) are left out
+    fn in_dir(device: &Path, busy: Busy, fields: &[GpuField]) -> Self {
+        let open = |field, path: &Path| {
+            fields
+                .contains(&field)
+                .then(|| SysfsAttr::open(path).ok())
+                .flatten()
+        };
+
+        let vram = open(GpuField::Vram, &device.join("mem_info_vram_used"))
+            .zip(open(GpuField::Vram, &device.join("mem_info_vram_total")));
+
+        // the card's own hwmon chip, e.g. amdgpu
+        let chip = hwmon::chips(&device.join("hwmon"))
+            .and_then(|chips| chips.into_iter().next())
+            .map(|(_, path)| path);
+        let temp = chip
+            .as_ref()
+            .and_then(|chip| open(GpuField::Temp, &chip.join("temp1_input")));
+        let power = chip
+            .as_ref()
+            .and_then(|chip| open(GpuField::Power, &chip.join("power1_average")));
+
+        let clock = open(GpuField::Clock, &device.join("pp_dpm_sclk"));
+
+        Self {
+            busy,
+            vram,
+            temp,
+            power,
+            clock,
+        }
+    }
+
+    fn read(&self) -> Reading {
+        Reading {
+            busy: match &self.busy {
+                Busy::Sysfs(busy) => busy.read().ok(),
+                Busy::Fdinfo(usage) => usage
+                    .borrow_mut()
+                    .sample(Instant::now())
+                    .map(|usage| (usage * 100.).round() as u8),
+            },
+            vram: self.vram.as_ref().and_then(|(used, total)| {
+                let used: u64 = used.read().ok()?;
+                let total: u64 = total.read().ok()?;
+                (total > 0).then(|| used as f32 / total as f32)
+            }),
+            temp: self.temp.as_ref().and_then(|temp| temp.read().ok()),
+            power: self.power.as_ref().and_then(|power| power.read().ok()),
+            clock: self
+                .clock
+                .as_ref()
+                .and_then(|clock| clock.read_with(current_clock).ok().flatten()),
+        }
+    }
+}
+
+/// the clock level marked with `*` in pp_dpm_sclk, e.g. `1: 1900Mhz *`
+fn current_clock(levels: &[u8]) -> Option<u32> {
+    let line = levels
+        .split(|&b| b == b'\n')
+        .find(|line| line.trim_ascii_end().ends_with(b"*"))?;
+
+    let (_, level) = line.split_at(line.iter().position(|&b| b == b':')? + 1);
+    let level = level.trim_ascii().strip_suffix(b"*")?.trim_ascii();
+    let digits = level.iter().take_while(|b| b.is_ascii_digit()).count();
+    sysfs::parse(&level[..digits])
+}
+
+impl fmt::Display for Gpu {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reading = self.read();
+
+        let usage = reading.busy.unwrap_or(0);
+        write!(f, "{}", usage_bar(usage as f32 / 100.))?;
+
+        if let Some(vram) = reading.vram {
+            write!(f, "{}", Bg(USAGE_BG).chain(usage_bar(vram)))?;
+        }
+
+        if reading.temp.is_none() && reading.power.is_none() && reading.clock.is_none() {
+            return Ok(());
+        }
+
+        write!(f, "{}{}", Fg(crate::FG), Bg(crate::BG))?;
+
+        if let Some(temp) = reading.temp {
+            write!(f, " {:2}°C", temp / 1000)?;
+        }
+
+        if let Some(power) = reading.power {
+            write!(f, " {:3}W", power / 1_000_000)?;
+        }
+
+        if let Some(clock) = reading.clock {
+            write!(f, " {:4}MHz", clock)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("no gpu card matching `{spec}` with usage data, available: {available}")]
+pub struct NoSuchCard {
+    spec: String,
+    available: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::fixture;
+
+    const ALL: &[GpuField] = &[
+        GpuField::Vram,
+        GpuField::Temp,
+        GpuField::Power,
+        GpuField::Clock,
+    ];
+
+    fn sample_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let device = dir.path();
+        fixture::attrs(
+            device,
+            &[
+                ("gpu_busy_percent", "42"),
+                ("mem_info_vram_used", "1073741824"),
+                ("mem_info_vram_total", "4294967296"),
+            ],
+        );
+        fs::write(
+            device.join("pp_dpm_sclk"),
+            "0: 500Mhz \n1: 1900Mhz *\n2: 2400Mhz \n",
+        )
+        .unwrap();
+
+        fixture::attrs(
+            &device.join("hwmon").join("hwmon3"),
+            &[
+                ("name", "amdgpu"),
+                ("temp1_input", "52000"),
+                ("power1_average", "35000000"),
+            ],
+        );
+        dir
+    }
+
+    fn sysfs(device: &Path, fields: &[GpuField]) -> Gpu {
+        let busy = SysfsAttr::open(device.join("gpu_busy_percent")).unwrap();
+        Gpu::in_dir(device, Busy::Sysfs(busy), fields)
+    }
+
+    #[test]
+    fn all_fields() {
+        let dir = sample_tree();
+        let gpu = sysfs(dir.path(), ALL);
+
+        assert_eq!(
+            gpu.read(),
+            Reading {
+                busy: Some(42),
+                vram: Some(0.25),
+                temp: Some(52000),
+                power: Some(35000000),
+                clock: Some(1900),
+            }
+        );
+        assert!(gpu.to_string().ends_with(" 52°C  35W 1900MHz"));
+    }
+
+    #[test]
+    fn toggled_off() {
+        let dir = sample_tree();
+        let gpu = sysfs(dir.path(), &[GpuField::Temp]);
+
+        assert_eq!(
+            gpu.read(),
+            Reading {
+                busy: Some(42),
+                temp: Some(52000),
+                ..Reading::default()
+            }
+        );
+    }
+
+    #[test]
+    fn vram_above_total() {
+        let dir = sample_tree();
+        let gpu = sysfs(dir.path(), &[GpuField::Vram]);
+        fixture::attrs(dir.path(), &[("mem_info_vram_used", "4294967296")]);
+        let full = gpu.to_string();
+
+        // e.g. when the counters race, shown as a full bar
+        fixture::attrs(dir.path(), &[("mem_info_vram_used", "8589934592")]);
+        assert_eq!(gpu.read().vram, Some(2.));
+        assert_eq!(gpu.to_string(), full);
+    }
+
+    #[test]
+    fn missing_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("gpu_busy_percent"), "7\n").unwrap();
+        let gpu = sysfs(dir.path(), ALL);
+        assert_eq!(
+            gpu.read(),
+            Reading {
+                busy: Some(7),
+                ..Reading::default()
+            }
+        );
+    }
+
+    struct Pci<'a> {
+        pdev: &'a str,
+        driver: &'a str,
+        vendor: &'a str,
+        device: &'a str,
+    }
+
+    fn card(drm: &Path, name: &str, pci: Pci, busy: Option<u8>) {
+        let Pci {
+            pdev,
+            driver,
+            vendor,
+            device,
+        } = pci;
+
+        let dir = drm.join("devices").join(pdev);
+        fixture::attrs(&dir, &[("vendor", vendor), ("device", device)]);
+        fixture::symlink(&dir, &drm.join(name).join("device"));
+
+        let driver = drm.join("drivers").join(driver);
+        fs::create_dir_all(&driver).unwrap();
+        fixture::symlink(driver, &dir.join("driver"));
+
+        if let Some(busy) = busy {
+            fixture::attrs(&dir, &[("gpu_busy_percent", busy)]);
+        }
+    }
+
+    fn drm() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let amd = |pdev, device| Pci {
+            pdev,
+            driver: "amdgpu",
+            vendor: "0x1002",
+            device,
+        };
+        let intel = Pci {
+            pdev: "0000:00:02.0",
+            driver: "i915",
+            vendor: "0x8086",
+            device: "0x9a49",
+        };
+        card(dir.path(), "card1", amd("0000:03:00.0", "0x73df"), Some(30));
+        card(dir.path(), "card0", intel, None);
+        card(dir.path(), "card2", amd("0000:06:00.0", "0x1638"), Some(5));
+        fs::create_dir_all(dir.path().join("card1-DP-1")).unwrap();
+        dir
+    }
+
+    fn busy(gpus: &Gpus) -> Vec<Option<u8>> {
+        gpus.cards.iter().map(|gpu| gpu.read().busy).collect()
+    }
+
+    #[test]
+    fn select_cards() {
+        let dir = drm();
+        let proc = tempfile::tempdir().unwrap();
+        let open = |spec| Gpus::open(dir.path(), proc.path(), spec, &[]);
+
+        // fdinfo needs 2 samples
+        assert_eq!(busy(&open("auto").unwrap()), [None, Some(30), Some(5)]);
+        assert_eq!(busy(&open("i915").unwrap()), [None]);
+        assert_eq!(busy(&open("card2").unwrap()), [Some(5)]);
+        assert_eq!(busy(&open("amdgpu").unwrap()), [Some(30), Some(5)]);
+        assert_eq!(busy(&open("1002:73DF").unwrap()), [Some(30)]);
+        assert_eq!(busy(&open("0x1002").unwrap()), [Some(30), Some(5)]);
+
+        let err = open("card7").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "no gpu card matching `card7` with usage data, available: card0 (i915 8086:9a49), \
+             card1 (amdgpu 1002:73df), card2 (amdgpu 1002:1638)"
+        );
+    }
+
+    #[test]
+    fn clock_levels() {
+        assert_eq!(current_clock(b"0: 500Mhz *\n1: 800Mhz"), Some(500));
+        assert_eq!(current_clock(b"0: 500Mhz\n1: 800Mhz"), None);
+        assert_eq!(
+            current_clock(b"S: 19Mhz\n0: 200Mhz\n1: 1300MHz *"),
+            Some(1300)
+        );
+    }
+}
@@ -0,0 +1,174 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use super::fdinfo::{self, Sampler};
+use crate::color::Color;
+use crate::component::{ByteSize, Fg, Sanitized};
+
+/// the process that uses the GPU the most, from the fdinfo of every process, refreshed every `interval`
+pub struct GpuTop {
+    proc: PathBuf,
+    interval: Duration,
+    sampler: RefCell<Sampler>,
+    top: RefCell<Option<Consumer>>,
+}
+
+#[derive(Debug, PartialEq)]
+struct Consumer {
+    name: String,
+    /// busy share (0-1) of its busiest engine
+    share: f32,
+    /// bytes of GPU memory
+    memory: u64,
+}
+
+impl GpuTop {
+    pub fn new(interval: Duration) -> Self {
+        Self::in_dir(Path::new(fdinfo::PROC), interval)
+    }
+
+    fn in_dir(proc: &Path, interval: Duration) -> Self {
+        Self {
+            proc: proc.to_path_buf(),
+            interval,
+            sampler: RefCell::new(Sampler::new(proc.to_path_buf(), None)),
+            top: RefCell::new(None),
+        }
+    }
+
+    /// the top consumer since the previous sample, None on the first one or if the GPU is idle
+    fn sample(&self, now: Instant) -> Option<Consumer> {
+        let usage = self.sampler.borrow_mut().sample(now)?;
+
+        // per pid, device and engine, as a process can have several clients
+        let mut busy: HashMap<(u32, &str, &str), f64> = HashMap::new();
+        let mut memory: HashMap<u32, u64> = HashMap::new();
+        for client in &usage {
+            *memory.entry(client.pid).or_default() += client.memory;
+            for (engine, share) in &client.engines {
+                *busy.entry((client.pid, &client.pdev, engine)).or_default() += share;
+            }
+        }
+
+        let mut shares: HashMap<u32, f64> = HashMap::new();
+        for ((pid, _, _), share) in busy {
+            let max = shares.entry(pid).or_default();
+            *max = max.max(share);
+        }
+
+        let (pid, share) = shares
+            .into_iter()
+            .filter(|(_, share)| *share > 0.)
+            .max_by(|a, b| a.1.total_cmp(&b.1).then(b.0.cmp(&a.0)))?;
+
+        // the process may have exited since
+        let name = fs::read_to_string(self.proc.join(pid.to_string()).join("comm"))
+            .map(|name| name.trim().to_string())
+            .unwrap_or_else(|_| pid.to_string());
+
+        Some(Consumer {
+            name,
+            share: share.min(1.) as f32,
+            memory: memory.get(&pid).copied().unwrap_or(0),
+        })
+    }
+}
+
+impl fmt::Display for GpuTop {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let now = Instant::now();
+        let due = self
+            .sampler
+            .borrow()
+            .sampled()
+            .is_none_or(|then| now.duration_since(then) >= self.interval);
+        if due {
+            let top = self.sample(now);
+            *self.top.borrow_mut() = top;
+        }
+
+        let Some(top) = &*self.top.borrow() else {
+            return write!(f, "{}-{}", Fg(Color::DIM), Fg(crate::FG));
+        };
+
+        let share = (top.share * 100.).round() as u8;
+        write!(f, "{} {:3}%", Sanitized(&top.name), share)?;
+        if top.memory > 0 {
+            write!(f, " {}", ByteSize(top.memory))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::fdinfo::tests::{fd, i915};
+    use super::*;
+    use crate::fixture;
+
+    fn process(proc: &Path, pid: u32, name: &str) {
+        fixture::attrs(&proc.join(pid.to_string()), &[("comm", name)]);
+    }
+
+    #[test]
+    fn top_consumer() {
+        let start = Instant::now();
+        let dir = tempfile::tempdir().unwrap();
+        let proc = dir.path();
+        process(proc, 100, "firefox");
+        process(proc, 200, "mpv");
+        fd(proc, 100, 7, "/dev/dri/renderD128", &i915(1, 0, 0));
+        fd(proc, 200, 4, "/dev/dri/renderD128", &i915(2, 0, 0));
+        // another user's process
+        fs::create_dir_all(proc.join("300")).unwrap();
+
+        let top = GpuTop::in_dir(proc, Duration::from_secs(5));
+        assert_eq!(top.sample(start), None);
+
+        // firefox renders for 200ms, mpv decodes for 1s on 1 of the 2 video engines
+        fs::write(proc.join("100/fdinfo/7"), i915(1, 200_000_000, 0)).unwrap();
+        fs::write(proc.join("200/fdinfo/4"), i915(2, 0, 1_000_000_000)).unwrap();
+        let consumer = top.sample(start + Duration::from_secs(1)).unwrap();
+        assert_eq!(consumer.name, "mpv");
+        assert!((consumer.share - 0.5).abs() < 1e-6);
+
+        // idle
+        let consumer = top.sample(start + Duration::from_secs(2));
+        assert_eq!(consumer, None);
+    }
+
+    #[test]
+    fn sanitized_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let proc = dir.path();
+        process(proc, 100, "%{F:FF0000}evil");
+        fd(proc, 100, 7, "/dev/dri/renderD128", &i915(1, 0, 0));
+
+        let top = GpuTop::in_dir(proc, Duration::ZERO);
+        top.to_string();
+
+        fs::write(proc.join("100/fdinfo/7"), i915(1, 500_000_000, 0)).unwrap();
+        assert!(top.to_string().starts_with("{F:FF0000}evil "));
+    }
+
+    #[test]
+    fn exited_process() {
+        let start = Instant::now();
+        let dir = tempfile::tempdir().unwrap();
+        let proc = dir.path();
+        fd(proc, 100, 7, "/dev/dri/renderD128", &i915(1, 0, 0));
+
+        let top = GpuTop::in_dir(proc, Duration::from_secs(5));
+        assert_eq!(top.sample(start), None);
+
+        // no comm anymore
+        fs::write(proc.join("100/fdinfo/7"), i915(1, 500_000_000, 0)).unwrap();
+        let consumer = top.sample(start + Duration::from_secs(1)).unwrap();
+        assert_eq!(consumer.name, "100");
+    }
+}
@@ -0,0 +1,349 @@
+use std::cell::Cell;
+use std::process::{Command, Stdio};
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// the metrics that hooks can be attached to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    /// battery capacity, in percents
+    Battery,
+
+    /// temperature, in °C
+    Temperature,
+
+    /// RAM usage, in percents
+    Memory,
+
+    /// 1 minute load average
+    Load,
+}
+
+impl Metric {
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Battery => "battery",
+            Self::Temperature => "temperature",
+            Self::Memory => "memory",
+            Self::Load => "load",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    Below,
+    Above,
+}
+
+/// a command that runs whenever a metric crosses a threshold.
+///
+/// parsed from `METRIC<THRESHOLD[~HYSTERESIS][@COOLDOWN]:COMMAND` (or with `>`), e.g.
+/// `battery<15:notify-send "low battery"` or `temperature>90~5@300:warn.sh`.
+/// after firing, the hook is re-armed only once the metric goes back past the threshold by at
+/// least HYSTERESIS (default 1), and it never fires twice within COOLDOWN seconds (default 0).
+#[derive(Debug, Clone)]
+pub struct Hook {
+    pub metric: Metric,
+    pub direction: Direction,
+    pub threshold: f32,
+    pub hysteresis: f32,
+    pub cooldown: Duration,
+    pub command: String,
+}
+
+impl FromStr for Hook {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (condition, command) = s
+            .split_once(':')
+            .ok_or("expected METRIC<THRESHOLD:COMMAND")?;
+
+        let split = condition
+            .find(['<', '>'])
+            .ok_or("expected either < or > after the metric")?;
+
+        let metric = match condition[..split].trim() {
+            "battery" => Metric::Battery,
+            "temperature" => Metric::Temperature,
+            "memory" => Metric::Memory,
+            "load" => Metric::Load,
+            other => return Err(format!("unknown metric `{other}`")),
+        };
+
+        let direction = if condition[split..].starts_with('<') {
+            Direction::Below
+        } else {
+            Direction::Above
+        };
+
+        let rest = &condition[split + 1..];
+        let (rest, cooldown) = match rest.split_once('@') {
+            Some((rest, cooldown)) => (rest, Some(cooldown)),
+            None => (rest, None),
+        };
+        let (threshold, hysteresis) = match rest.split_once('~') {
+            Some((threshold, hysteresis)) => (threshold, Some(hysteresis)),
+            None => (rest, None),
+        };
+
+        let number = |text: &str| {
+            let number = text
+                .trim()
+                .parse::<f32>()
+                .map_err(|err| format!("invalid number `{text}`: {err}"))?;
+            if !number.is_finite() {
+                return Err(format!("`{text}` is not a finite number"));
+            }
+            Ok(number)
+        };
+        let non_negative = |text: &str| {
+            let number = number(text)?;
+            if number < 0. {
+                return Err(format!("`{text}` must not be negative"));
+            }
+            Ok(number)
+        };
+
+        let cooldown = cooldown.map(non_negative).transpose()?.unwrap_or(0.);
+        let cooldown = Duration::try_from_secs_f32(cooldown)
+            .map_err(|err| format!("invalid cooldown `{cooldown}`: {err}"))?;
+
+        Ok(Self {
+            metric,
+            direction,
+            threshold: number(threshold)?,
+            hysteresis: hysteresis.map(non_negative).transpose()?.unwrap_or(1.),
+            cooldown,
+            command: command.trim().to_string(),
+        })
+    }
+}
+
+struct Trigger {
+    hook: Hook,
+    armed: Cell<bool>,
+    last_fired: Cell<Option<Instant>>,
+}
+
+impl Trigger {
+    fn new(hook: Hook) -> Self {
+        Self {
+            hook,
+            armed: Cell::new(true),
+            last_fired: Cell::new(None),
+        }
+    }
+
+    /// whether the hook should fire for `value` at `now`
+    fn update(&self, value: f32, now: Instant) -> bool {
+        let hook = &self.hook;
+        let (crossed, recovered) = match hook.direction {
+            Direction::Below => (
+                value < hook.threshold,
+                value >= hook.threshold + hook.hysteresis,
+            ),
+            Direction::Above => (
+                value > hook.threshold,
+                value <= hook.threshold - hook.hysteresis,
+            ),
+        };
+
+        if recovered {
+            self.armed.set(true);
+            return false;
+        }
+
+        if !crossed || !self.armed.get() {
+            return false;
+        }
+
+        let cooling_down = self
+            .last_fired
+            .get()
+            .is_some_and(|last| now.duration_since(last) < hook.cooldown);
+        if cooling_down {
+            return false;
+        }
+
+        self.armed.set(false);
+        self.last_fired.set(Some(now));
+        true
+    }
+
+    /// re-arms the hook if `value` went back past the hysteresis, without firing
+    fn observe(&self, value: f32) {
+        let hook = &self.hook;
+        let recovered = match hook.direction {
+            Direction::Below => value >= hook.threshold + hook.hysteresis,
+            Direction::Above => value <= hook.threshold - hook.hysteresis,
+        };
+        if recovered {
+            self.armed.set(true);
+        }
+    }
+}
+
+/// a hook on a metric whose component isn't shown, so it would never fire
+#[derive(Debug, thiserror::Error)]
+#[error("the {} hook needs {flag}", .metric.name())]
+pub struct DisabledMetric {
+    pub metric: Metric,
+    /// the argument that enables the component
+    pub flag: &'static str,
+}
+
+/// the hooks of a single metric
+#[derive(Default)]
+pub struct Hooks {
+    triggers: Vec<Trigger>,
+}
+
+impl Hooks {
+    /// only the hooks of `metric` are taken
+    pub fn new(hooks: &[Hook], metric: Metric) -> Self {
+        let triggers = hooks
+            .iter()
+            .filter(|hook| hook.metric == metric)
+            .map(|hook| Trigger::new(hook.clone()))
+            .collect();
+
+        Self { triggers }
+    }
+
+    /// fires the hooks for which `value` crossed their threshold
+    pub fn update(&self, value: f32) {
+        let now = Instant::now();
+        for trigger in &self.triggers {
+            if trigger.update(value, now) {
+                run(&trigger.hook.command);
+            }
+        }
+    }
+
+    /// only re-arms the hooks, for when they shouldn't fire, e.g. while the battery is charging
+    pub fn observe(&self, value: f32) {
+        for trigger in &self.triggers {
+            trigger.observe(value);
+        }
+    }
+}
+
+fn run(command: &str) {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        // stdout is where the bar is written to
+        .stdout(Stdio::null())
+        .spawn();
+
+    match child {
+        // wait in the background, so the bar isn't blocked and no zombies are left behind
+        Ok(mut child) => {
+            thread::spawn(move || child.wait());
+        }
+
+        Err(err) => eprintln!("failed to run hook `{command}`: {err}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trigger(spec: &str) -> Trigger {
+        Trigger::new(spec.parse().unwrap())
+    }
+
+    #[test]
+    fn parse() {
+        let hook: Hook = "temperature>90~5@300:warn.sh --now".parse().unwrap();
+        assert_eq!(hook.metric, Metric::Temperature);
+        assert_eq!(hook.direction, Direction::Above);
+        assert_eq!(hook.threshold, 90.);
+        assert_eq!(hook.hysteresis, 5.);
+        assert_eq!(hook.cooldown, Duration::from_secs(300));
+        assert_eq!(hook.command, "warn.sh --now");
+
+        let hook: Hook = "battery<15:notify-send \"a:b\"".parse().unwrap();
+        assert_eq!(hook.direction, Direction::Below);
+        assert_eq!(hook.hysteresis, 1.);
+        assert_eq!(hook.cooldown, Duration::ZERO);
+        assert_eq!(hook.command, "notify-send \"a:b\"");
+    }
+
+    #[test]
+    fn parse_errors() {
+        for spec in [
+            "battery<15",
+            "battery=15:true",
+            "disk<15:true",
+            "battery<abc:true",
+            "battery<15@-1:true",
+            "battery<15@inf:true",
+            "battery<15@1e30:true",
+            "battery<NaN:true",
+            "battery<inf:true",
+            "battery<15~-2:true",
+            "battery<15~inf:true",
+        ] {
+            assert!(spec.parse::<Hook>().is_err(), "{spec}");
+        }
+    }
+
+    #[test]
+    fn fires_once_per_crossing() {
+        let now = Instant::now();
+        let trigger = trigger("battery<15~5:true");
+
+        assert!(!trigger.update(20., now));
+        assert!(trigger.update(14., now));
+        assert!(!trigger.update(10., now));
+
+        // not past the hysteresis
+        assert!(!trigger.update(18., now));
+        assert!(!trigger.update(14., now));
+
+        assert!(!trigger.update(20., now));
+        assert!(trigger.update(14., now));
+    }
+
+    #[test]
+    fn above() {
+        let now = Instant::now();
+        let trigger = trigger("load>4~0.5:true");
+
+        assert!(trigger.update(4.5, now));
+        assert!(!trigger.update(3.8, now));
+        assert!(!trigger.update(4.5, now));
+        assert!(!trigger.update(3.5, now));
+        assert!(trigger.update(4.5, now));
+    }
+
+    #[test]
+    fn cooldown() {
+        let start = Instant::now();
+        let trigger = trigger("memory>90~1@60:true");
+
+        assert!(trigger.update(95., start));
+        assert!(!trigger.update(50., start + Duration::from_secs(10)));
+        assert!(!trigger.update(95., start + Duration::from_secs(20)));
+
+        // still armed after the suppressed crossing
+        assert!(trigger.update(95., start + Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn observe_only_rearms() {
+        let now = Instant::now();
+        let trigger = trigger("battery<5:true");
+
+        assert!(trigger.update(4., now));
+        trigger.observe(3.);
+        trigger.observe(80.);
+        assert!(trigger.update(4., now));
+    }
+}
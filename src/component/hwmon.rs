@@ -0,0 +1,128 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const HWMON: &str = "/sys/class/hwmon";
+
+/// the hwmon chips in `dir` along with their names, sorted by path
+pub fn chips(dir: &Path) -> Option<Vec<(String, PathBuf)>> {
+    let mut chips: Vec<_> = dir
+        .read_dir()
+        .ok()?
+        .flatten()
+        .filter(|entry| entry.file_name().as_encoded_bytes().starts_with(b"hwmon"))
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = fs::read_to_string(path.join("name")).ok()?;
+            Some((name.trim().to_string(), path))
+        })
+        .collect();
+
+    chips.sort_by(|a, b| a.1.cmp(&b.1));
+    Some(chips)
+}
+
+/// the indices of the `{kind}N_input` files of a chip (e.g. temp1_input), sorted
+pub fn inputs(chip: &Path, kind: &str) -> Vec<u32> {
+    let mut indices: Vec<_> = chip
+        .read_dir()
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name();
+            let name = name.to_str()?;
+            name.strip_prefix(kind)?
+                .strip_suffix("_input")?
+                .parse()
+                .ok()
+        })
+        .collect();
+
+    indices.sort_unstable();
+    indices
+}
+
+/// the content of `{kind}N_label`, e.g. `Package id 0` for coretemp's temp1_label
+pub fn label(chip: &Path, kind: &str, index: u32) -> Option<String> {
+    let label = fs::read_to_string(chip.join(format!("{kind}{index}_label"))).ok()?;
+    Some(label.trim().to_string())
+}
+
+/// which inputs of a chip to use
+pub struct Selection {
+    pub indices: Vec<u32>,
+    /// whether only the highest of them should be shown
+    pub max: bool,
+}
+
+#[derive(Debug)]
+pub enum SelectError {
+    /// the chip has no inputs of that kind
+    Empty,
+
+    /// none of the inputs has that label
+    NoSuchLabel { label: String, available: String },
+}
+
+/// selects the `{kind}N_input` files of a chip, where `selection` is either a comma separated
+/// list of `{kind}N_label` values or `max` for all of them (keeping only the highest).
+/// without a selection, the first input is used.
+pub fn select(chip: &Path, kind: &str, selection: Option<&str>) -> Result<Selection, SelectError> {
+    let indices = inputs(chip, kind);
+    if indices.is_empty() {
+        return Err(SelectError::Empty);
+    }
+
+    let selection = match selection {
+        None => Selection {
+            indices: vec![indices[0]],
+            max: false,
+        },
+
+        Some("max") => Selection { indices, max: true },
+
+        Some(labels) => {
+            let labeled: Vec<_> = indices
+                .iter()
+                .filter_map(|&index| Some((index, label(chip, kind, index)?)))
+                .collect();
+
+            let indices = labels
+                .split(',')
+                .map(|label| {
+                    labeled
+                        .iter()
+                        .find(|(_, other)| other == label.trim())
+                        .map(|(index, _)| *index)
+                        .ok_or_else(|| SelectError::NoSuchLabel {
+                            label: label.trim().to_string(),
+                            available: labeled
+                                .iter()
+                                .map(|(_, label)| label.as_str())
+                                .collect::<Vec<_>>()
+                                .join(", "),
+                        })
+                })
+                .collect::<Result<_, _>>()?;
+
+            Selection {
+                indices,
+                max: false,
+            }
+        }
+    };
+
+    Ok(selection)
+}
+
+/// the names of the chips, without duplicates, for error messages
+pub fn names<'a>(chips: impl IntoIterator<Item = &'a (String, PathBuf)>) -> String {
+    let mut names = Vec::new();
+    for (name, _) in chips {
+        if !names.contains(&name.as_str()) {
+            names.push(name.as_str());
+        }
+    }
+
+    names.join(", ")
+}
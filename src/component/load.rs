@@ -0,0 +1,247 @@
+use std::cell::RefCell;
+use std::{fmt, io};
+
+use crate::SYS;
+use crate::color::Color;
+use crate::component::Fg;
+use crate::component::hook::Hooks;
+use crate::component::temperature::{COOL, HOT, WARM};
+use crate::metrics::ProcFile;
+
+const LOADAVG: &str = "/proc/loadavg";
+
+/// the 1, 5 and 15 minute load averages, and the running/total task counts
+pub struct Load {
+    loadavg: RefCell<ProcFile>,
+    cpus: usize,
+    normalize: bool,
+    hooks: Hooks,
+}
+
+impl Load {
+    /// `normalize` divides the load averages by the number of cpus
+    pub fn new(normalize: bool) -> Result<Self, LoadError> {
+        let loadavg = ProcFile::open(LOADAVG).map_err(|_| LoadError::NoLoadAvg)?;
+
+        let cpus = {
+            let mut sys = SYS.lock().unwrap();
+            sys.refresh_cpu_usage().map_err(LoadError::Cpus)?;
+            sys.cpu_usage().len().max(1)
+        };
+
+        Ok(Self {
+            loadavg: RefCell::new(loadavg),
+            cpus,
+            normalize,
+            hooks: Hooks::default(),
+        })
+    }
+
+    /// `hooks` are fired with the 1 minute load average (normalized, if enabled)
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+}
+
+/// the contents of /proc/loadavg, e.g. `0.52 0.58 0.59 2/1234 56789`
+#[derive(Debug, PartialEq)]
+struct LoadAvg {
+    loads: [f32; 3],
+    running: u32,
+    total: u32,
+}
+
+impl LoadAvg {
+    fn parse(text: &str) -> Option<Self> {
+        let mut words = text.split_ascii_whitespace();
+        let mut load = || words.next()?.parse().ok();
+        let loads = [load()?, load()?, load()?];
+
+        let (running, total) = words.next()?.split_once('/')?;
+        Some(Self {
+            loads,
+            running: running.parse().ok()?,
+            total: total.parse().ok()?,
+        })
+    }
+}
+
+impl fmt::Display for Load {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut loadavg = self.loadavg.borrow_mut();
+        let Some(loadavg) = loadavg.read().ok().and_then(LoadAvg::parse) else {
+            return write!(f, "{}-{}", Fg(Color::DIM), Fg(crate::FG));
+        };
+
+        for (i, load) in loadavg.loads.into_iter().enumerate() {
+            let per_cpu = load / self.cpus as f32;
+            let shown = if self.normalize { per_cpu } else { load };
+            if i == 0 {
+                self.hooks.update(shown);
+            }
+
+            write!(f, "{}{} ", Fg(load_color(per_cpu)), LoadValue(shown))?;
+        }
+
+        write!(
+            f,
+            "{}{:>3}/{:>4}",
+            Fg(crate::FG),
+            TaskCount(loadavg.running),
+            TaskCount(loadavg.total)
+        )
+    }
+}
+
+fn load_color(per_cpu: f32) -> Color {
+    if per_cpu < 0.25 {
+        COOL
+    } else if per_cpu < 0.5 {
+        crate::FG
+    } else if per_cpu < 1. {
+        WARM
+    } else {
+        HOT
+    }
+}
+
+/// a load average, always 4 characters wide
+struct LoadValue(f32);
+
+impl fmt::Display for LoadValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.0 < 9.995 {
+            write!(f, "{:4.2}", self.0)
+        } else if self.0 < 99.95 {
+            write!(f, "{:4.1}", self.0)
+        } else {
+            write!(f, "{:4.0}", self.0)
+        }
+    }
+}
+
+/// a task count, in thousands or millions if it's wider than the width it's formatted with
+struct TaskCount(u32);
+
+impl fmt::Display for TaskCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let width = f.width().unwrap_or(usize::MAX);
+
+        let count = self.0.to_string();
+        if count.len() <= width {
+            return f.pad(&count);
+        }
+
+        let thousands = format!("{}k", self.0 / 1000);
+        if thousands.len() <= width {
+            return f.pad(&thousands);
+        }
+
+        f.pad(&format!("{}M", self.0 / 1_000_000))
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum LoadError {
+    #[error("unable to read /proc/loadavg")]
+    NoLoadAvg,
+
+    #[error("unable to count the cpus: {0}")]
+    Cpus(#[source] io::Error),
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::fixture;
+
+    const LOADAVG: &str = "0.52 1.58 4.59 2/1234 56789\n";
+
+    fn load(text: &str, cpus: usize, normalize: bool) -> (tempfile::TempDir, Load) {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("loadavg");
+        fs::write(&path, text).unwrap();
+
+        let load = Load {
+            loadavg: RefCell::new(ProcFile::open(path).unwrap()),
+            cpus,
+            normalize,
+            hooks: Hooks::default(),
+        };
+        (dir, load)
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            LoadAvg::parse(LOADAVG),
+            Some(LoadAvg {
+                loads: [0.52, 1.58, 4.59],
+                running: 2,
+                total: 1234,
+            })
+        );
+        assert_eq!(LoadAvg::parse("0.52 1.58 4.59\n"), None);
+        assert_eq!(LoadAvg::parse("0.52 1.58 4.59 2 56789\n"), None);
+        assert_eq!(LoadAvg::parse(""), None);
+    }
+
+    #[test]
+    fn absolute() {
+        let (_dir, load) = load(LOADAVG, 4, false);
+        assert_eq!(
+            load.to_string(),
+            format!(
+                "{}0.52 {}1.58 {}4.59 {}  2/1234",
+                Fg(COOL),
+                Fg(crate::FG),
+                Fg(HOT),
+                Fg(crate::FG)
+            )
+        );
+    }
+
+    #[test]
+    fn colors() {
+        assert_eq!(load_color(0.24), COOL);
+        assert_eq!(load_color(0.25), crate::FG);
+        assert_eq!(load_color(0.5), WARM);
+        assert_eq!(load_color(1.), HOT);
+    }
+
+    #[test]
+    fn normalized() {
+        let (_dir, load) = load(LOADAVG, 4, true);
+        assert_eq!(fixture::plain(&load.to_string()), "0.13 0.40 1.15   2/1234");
+    }
+
+    #[test]
+    fn fixed_width() {
+        let shown = |text| fixture::plain(&load(text, 1, false).1.to_string());
+        for text in [
+            "0.00 9.99 10.00 1/1\n",
+            "99.94 99.96 1234.5 999/9999\n",
+            "1.00 1.00 1.00 1000/10000\n",
+            "1.00 1.00 1.00 12/4194304\n",
+        ] {
+            assert_eq!(shown(text).chars().count(), 23, "{text}");
+        }
+
+        assert_eq!(
+            shown("1.00 1.00 1.00 2/123456\n"),
+            "1.00 1.00 1.00   2/123k"
+        );
+        assert_eq!(
+            shown("1.00 1.00 1.00 2/4194304\n"),
+            "1.00 1.00 1.00   2/  4M"
+        );
+        assert_eq!(
+            shown("1.00 1.00 1.00 1024/4096\n"),
+            "1.00 1.00 1.00  1k/4096"
+        );
+        assert_eq!(shown("garbage\n"), "-");
+    }
+}
@@ -1,17 +1,266 @@
-use std::fmt;
-
-use sysinfo::MemoryRefreshKind;
+use std::path::Path;
+use std::{fmt, str};
 
 use crate::SYS;
+use crate::color::Color;
+use crate::component::hook::Hooks;
+use crate::component::{Bg, ByteSize, DisplayExt as _, Fg, USAGE_BG, usage_bar};
+use crate::metrics::MemInfo;
+use crate::sysfs::SysfsAttr;
+
+const BLOCK: &str = "/sys/block";
+
+#[derive(Debug, Default, Clone, Copy, clap::ValueEnum)]
+pub enum MemoryFormat {
+    /// used memory, e.g. `5.3G`
+    #[default]
+    Used,
+
+    /// used and total memory, e.g. `5.3G/ 16G`
+    UsedTotal,
+
+    /// used memory in percents, e.g. ` 33%`
+    Percent,
+}
+
+pub struct Memory {
+    format: MemoryFormat,
+    include_cache: bool,
+    bar: bool,
+    warn: u8,
+    hooks: Hooks,
+}
 
-pub struct Memory;
+impl Memory {
+    /// `include_cache` counts the page cache and buffers as used memory.
+    /// the label turns red when the usage is at least `warn` percents.
+    pub fn new(format: MemoryFormat, include_cache: bool, bar: bool, warn: u8) -> Self {
+        Self {
+            format,
+            include_cache,
+            bar,
+            warn,
+            hooks: Hooks::default(),
+        }
+    }
+
+    /// `hooks` are fired with the RAM usage in percents
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+}
 
 impl fmt::Display for Memory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let memory = {
+            let mut sys = SYS.lock().unwrap();
+            sys.refresh_memory().unwrap();
+            *sys.memory()
+        };
+
+        self.write(f, &memory)
+    }
+}
+
+impl Memory {
+    fn write(&self, f: &mut fmt::Formatter, memory: &MemInfo) -> fmt::Result {
+        let total = memory.total;
+        let used = if self.include_cache {
+            memory.used_with_cache()
+        } else {
+            memory.used()
+        };
+
+        let usage = ratio(used, total);
+        self.hooks.update(usage * 100.);
+
+        write!(f, "{}", warn_label("RAM ", usage, self.warn))?;
+
+        match self.format {
+            MemoryFormat::Used => write!(f, "{}", ByteSize(used))?,
+            MemoryFormat::UsedTotal => write!(f, "{}/{}", ByteSize(used), ByteSize(total))?,
+            MemoryFormat::Percent => write!(f, "{:3.0}%", usage * 100.)?,
+        }
+
+        if self.bar {
+            let bar = Bg(USAGE_BG).chain(usage_bar(usage));
+            write!(f, " {}{}{}", bar, Fg(crate::FG), Bg(crate::BG))?;
+        }
+
+        Ok(())
+    }
+}
+
+/// swap usage, along with the memory used by zram devices (if there are any)
+pub struct Swap {
+    zram: Vec<SysfsAttr>,
+    warn: u8,
+}
+
+impl Swap {
+    /// the label turns red when the swap usage is at least `warn` percents
+    pub fn new(warn: u8) -> Self {
+        let zram = Path::new(BLOCK)
+            .read_dir()
+            .into_iter()
+            .flatten()
+            .flatten()
+            .filter(|entry| entry.file_name().as_encoded_bytes().starts_with(b"zram"))
+            .filter_map(|entry| SysfsAttr::open(entry.path().join("mm_stat")).ok())
+            .collect();
+
+        Self { zram, warn }
+    }
+}
+
+impl fmt::Display for Swap {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let mut sys = SYS.lock().unwrap();
-        sys.refresh_memory_specifics(MemoryRefreshKind::nothing().with_ram());
-        let bytes = sys.used_memory();
-        let mb = bytes >> 20;
-        write!(f, "{:5}", mb)
+        sys.refresh_memory().unwrap();
+        let memory = sys.memory();
+
+        let used = memory.swap_used();
+        let usage = ratio(used, memory.swap_total);
+        write!(
+            f,
+            "{}{}",
+            warn_label("SWP ", usage, self.warn),
+            ByteSize(used)
+        )?;
+
+        if self.zram.is_empty() {
+            return Ok(());
+        }
+
+        // orig_data_size compr_data_size mem_used_total ...
+        let zram_used: u64 = self
+            .zram
+            .iter()
+            .filter_map(|mm_stat| {
+                mm_stat
+                    .read_with(|stat| {
+                        let stat = str::from_utf8(stat).ok()?;
+                        stat.split_ascii_whitespace().nth(2)?.parse::<u64>().ok()
+                    })
+                    .ok()
+                    .flatten()
+            })
+            .sum();
+
+        write!(f, " {}{}", crate::label("ZRAM "), Fg(crate::FG))?;
+        write!(f, "{}", ByteSize(zram_used))
+    }
+}
+
+fn ratio(used: u64, total: u64) -> f32 {
+    if total == 0 {
+        0.
+    } else {
+        used as f32 / total as f32
+    }
+}
+
+fn warn_label(text: &str, usage: f32, warn: u8) -> impl fmt::Display {
+    let color = if usage * 100. >= warn as f32 {
+        Color::RED
+    } else {
+        Color::YELLOW
+    };
+
+    Fg(color).chain(text).chain(Fg(crate::FG))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture;
+
+    /// 16 GiB, of which `available` KiB are available and 2 GiB more are cached
+    fn meminfo(available: u64) -> MemInfo {
+        let text = format!(
+            "MemTotal:       16777216 kB\n\
+             MemFree:        {} kB\n\
+             MemAvailable:   {available} kB\n\
+             Buffers:          318404 kB\n\
+             Cached:          1778748 kB\n\
+             SwapTotal:       8388604 kB\n\
+             SwapFree:        8388604 kB\n",
+            available.saturating_sub(2 << 20)
+        );
+        MemInfo::parse(&text).unwrap()
+    }
+
+    struct Shown<'a>(&'a Memory, MemInfo);
+
+    impl fmt::Display for Shown<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            self.0.write(f, &self.1)
+        }
+    }
+
+    fn shown(memory: &Memory, available: u64) -> String {
+        Shown(memory, meminfo(available)).to_string()
+    }
+
+    #[test]
+    fn formats() {
+        let memory = |format| Memory::new(format, false, false, 90);
+        // 12 GiB used
+        let available = 4 << 20;
+
+        let used = shown(&memory(MemoryFormat::Used), available);
+        assert_eq!(fixture::plain(&used), "RAM  12G");
+        let used_total = shown(&memory(MemoryFormat::UsedTotal), available);
+        assert_eq!(fixture::plain(&used_total), "RAM  12G/ 16G");
+        let percent = shown(&memory(MemoryFormat::Percent), available);
+        assert_eq!(fixture::plain(&percent), "RAM  75%");
+
+        // including the cache
+        let memory = Memory::new(MemoryFormat::Used, true, false, 90);
+        assert_eq!(fixture::plain(&shown(&memory, available)), "RAM  14G");
+    }
+
+    #[test]
+    fn fixed_width() {
+        let formats = [
+            (MemoryFormat::Used, false, 8),
+            (MemoryFormat::UsedTotal, false, 13),
+            (MemoryFormat::Percent, false, 8),
+            (MemoryFormat::Percent, true, 9),
+        ];
+
+        for (format, bar, width) in formats {
+            let memory = Memory::new(format, false, bar, 90);
+            // from everything to nothing available
+            for available in [16 << 20, (16 << 20) - 1, 15 << 20, 10 << 20, 7 << 10, 0] {
+                let shown = fixture::plain(&shown(&memory, available));
+                assert_eq!(
+                    shown.chars().count(),
+                    width,
+                    "{format:?} {available}: {shown}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn bar() {
+        let memory = Memory::new(MemoryFormat::Percent, false, true, 90);
+        let full = shown(&memory, 0);
+        assert!(full.contains(&usage_bar(1.).to_string()), "{full}");
+        assert!(full.ends_with(&format!("{}{}", Fg(crate::FG), Bg(crate::BG))));
+    }
+
+    #[test]
+    fn warn_threshold() {
+        let memory = Memory::new(MemoryFormat::Percent, false, false, 75);
+        let label = |color| format!("{}RAM {}", Fg(color), Fg(crate::FG));
+
+        // 74% and 75% of 16 GiB used
+        let below = 16777216 - 16777216 * 74 / 100;
+        assert!(shown(&memory, below).starts_with(&label(Color::YELLOW)));
+        assert!(shown(&memory, 4 << 20).starts_with(&label(Color::RED)));
+        assert!(shown(&memory, 0).starts_with(&label(Color::RED)));
     }
 }
@@ -1,22 +1,38 @@
-use std::path::Path;
-use std::str::FromStr;
-use std::{fmt, fs, io};
+use std::fmt;
 
+pub mod address;
 pub mod battery;
 pub mod cpu;
+pub mod fan;
 pub mod gpu;
+pub mod hook;
+pub mod hwmon;
+pub mod load;
 pub mod memory;
+pub mod net;
+pub mod network;
+pub mod peripherals;
+pub mod pressure;
 pub mod temperature;
+pub mod throughput;
 pub mod time;
 pub mod wifi;
 
 use derive_more::Display;
 
+pub use address::*;
 pub use battery::*;
 pub use cpu::*;
+pub use fan::*;
 pub use gpu::*;
+pub use hook::*;
+pub use load::*;
 pub use memory::*;
+pub use network::*;
+pub use peripherals::*;
+pub use pressure::*;
 pub use temperature::*;
+pub use throughput::*;
 pub use time::*;
 pub use wifi::*;
 
@@ -59,6 +75,23 @@ impl<T> From<Option<T>> for DisplayOption<T> {
     }
 }
 
+/// text from outside of the bar, e.g. a wifi SSID, without control characters (which would break the line per frame)
+/// and `%` (which starts the bar's format codes)
+pub struct Sanitized<'a>(pub &'a str);
+
+impl fmt::Display for Sanitized<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use fmt::Write as _;
+
+        for c in self.0.chars() {
+            if !c.is_control() && c != '%' {
+                f.write_char(c)?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[derive(Display)]
 #[display("%{{F:{_0}}}")]
 pub struct Fg(pub Color);
@@ -86,17 +119,26 @@ pub struct Ramp {
     pub h: u32,
 }
 
-pub fn read_file<T>(path: impl AsRef<Path>) -> io::Result<T>
-where
-    T: FromStr,
-    <T as FromStr>::Err: std::error::Error + Send + Sync + 'static,
-{
-    let path = path.as_ref();
-    fs::read_to_string(path)?
-        .trim()
-        .parse()
-        .inspect_err(|e| eprintln!("failed to read {}: {}", path.display(), e))
-        .map_err(io::Error::other)
+/// a size in bytes, auto-scaled to a fixed width of 4 characters, e.g. ` 12K`, `1.3M`
+pub struct ByteSize(pub u64);
+
+impl fmt::Display for ByteSize {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const UNITS: &[char] = &['B', 'K', 'M', 'G', 'T', 'P'];
+
+        let mut value = self.0 as f64;
+        let mut unit = 0;
+        while value >= 999.5 && unit < UNITS.len() - 1 {
+            value /= 1024.;
+            unit += 1;
+        }
+
+        if unit > 0 && value < 9.95 {
+            write!(f, "{:3.1}{}", value, UNITS[unit])
+        } else {
+            write!(f, "{:3.0}{}", value, UNITS[unit])
+        }
+    }
 }
 
 pub const USAGE_BG: Color = Color(0x181818);
@@ -111,7 +153,9 @@ pub fn general_bar(usage: f32) -> Ramp {
     }
 }
 
+/// `usage` is clamped to 0-1, e.g. for drivers whose used memory exceeds the total
 pub fn usage_bar(usage: f32) -> impl fmt::Display {
+    let usage = usage.clamp(0., 1.);
     let height = usage * HEIGHT;
     let fg = Fg(USAGE_COLORS[height as usize]);
     let ramp = general_bar(usage);
@@ -119,6 +163,19 @@ pub fn usage_bar(usage: f32) -> impl fmt::Display {
     fg.chain(ramp)
 }
 
+const SPARK_WIDTH: u32 = 2;
+
+/// a narrow usage bar, for sparklines
+pub fn spark_bar(usage: f32) -> impl fmt::Display {
+    let height = usage.clamp(0., 1.) * HEIGHT;
+    let ramp = Ramp {
+        w: SPARK_WIDTH,
+        h: height as u32,
+    };
+
+    Fg(USAGE_COLORS[height as usize]).chain(ramp)
+}
+
 const USAGE_COLORS: &[Color] = &[
     Color(0x000000),
     Color(0x002F44),
@@ -150,14 +207,44 @@ const USAGE_COLORS: &[Color] = &[
 #[derive(Debug, thiserror::Error)]
 pub enum Error {
     #[error(transparent)]
-    Network(#[from] wifi::NoSuchInterface),
+    Network(#[from] net::NoSuchInterface),
 
     #[error(transparent)]
     Thermal(#[from] temperature::ThermalZoneError),
 
+    #[error(transparent)]
+    Fan(#[from] fan::FanError),
+
     #[error(transparent)]
     Gpu(#[from] gpu::NoSuchCard),
 
     #[error(transparent)]
     Battery(#[from] battery::NoSuchBattery),
+
+    #[error(transparent)]
+    Pressure(#[from] pressure::NoSuchCgroup),
+
+    #[error(transparent)]
+    Load(#[from] load::LoadError),
+
+    #[error(transparent)]
+    Hook(#[from] hook::DisabledMetric),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitized() {
+        let ssid = "free%{F:FF0000}wifi\n%{r}\u{1b}[0m";
+        assert_eq!(Sanitized(ssid).to_string(), "free{F:FF0000}wifi{r}[0m");
+        assert_eq!(Sanitized("Café ☕").to_string(), "Café ☕");
+    }
+
+    #[test]
+    fn usage_bar_out_of_range() {
+        assert_eq!(usage_bar(1.5).to_string(), usage_bar(1.).to_string());
+        assert_eq!(usage_bar(-0.5).to_string(), usage_bar(0.).to_string());
+    }
 }
@@ -0,0 +1,132 @@
+use std::cell::RefCell;
+use std::io;
+use std::path::Path;
+
+use crate::metrics::ProcFile;
+
+pub const NET: &str = "/sys/class/net";
+
+/// contains the routing tables, `route` and `ipv6_route`
+pub const PROC_NET: &str = "/proc/net";
+
+/// follows the interface of the default route
+pub const AUTO: &str = "auto";
+
+const RTF_UP: u32 = 0x1;
+const RTF_REJECT: u32 = 0x200;
+
+#[derive(Debug, Clone, Copy, thiserror::Error)]
+#[error("no such network interface")]
+pub struct NoSuchInterface;
+
+/// the interface of the default route, preferring IPv4 and falling back to IPv6 on IPv6-only networks
+pub struct DefaultRoute {
+    v4: RefCell<ProcFile>,
+    /// None when IPv6 is disabled
+    v6: Option<RefCell<ProcFile>>,
+}
+
+impl DefaultRoute {
+    pub fn open(proc_net: &Path) -> io::Result<Self> {
+        Ok(Self {
+            v4: RefCell::new(ProcFile::open(proc_net.join("route"))?),
+            v6: ProcFile::open(proc_net.join("ipv6_route"))
+                .ok()
+                .map(RefCell::new),
+        })
+    }
+
+    pub fn interface(&self) -> Option<String> {
+        let mut v4 = self.v4.borrow_mut();
+        if let Some(interface) = v4.read().ok().and_then(default_interface) {
+            return Some(interface.to_string());
+        }
+
+        let mut v6 = self.v6.as_ref()?.borrow_mut();
+        default_interface_v6(v6.read().ok()?).map(str::to_string)
+    }
+}
+
+/// the interface of the default route with the lowest metric, from the table in /proc/net/route
+fn default_interface(table: &str) -> Option<&str> {
+    table
+        .lines()
+        .skip(1)
+        .filter_map(|line| {
+            // Iface Destination Gateway Flags RefCnt Use Metric Mask MTU Window IRTT
+            let fields: Vec<_> = line.split_ascii_whitespace().collect();
+            let hex = |i: usize| u32::from_str_radix(fields.get(i)?, 16).ok();
+
+            let default = hex(1)? == 0 && hex(7)? == 0 && hex(3)? & RTF_UP != 0;
+            let metric: u32 = fields.get(6)?.parse().ok()?;
+            default.then(|| (metric, fields[0]))
+        })
+        .min_by_key(|(metric, _)| *metric)
+        .map(|(_, interface)| interface)
+}
+
+/// the interface of the ::/0 route with the lowest metric, from the table in /proc/net/ipv6_route
+fn default_interface_v6(table: &str) -> Option<&str> {
+    table
+        .lines()
+        .filter_map(|line| {
+            // Destination PrefixLen Source SourcePrefixLen NextHop Metric RefCnt Use Flags Iface
+            let fields: Vec<_> = line.split_ascii_whitespace().collect();
+            let hex = |i: usize| u32::from_str_radix(fields.get(i)?, 16).ok();
+
+            // the kernel adds an unreachable ::/0 route on lo
+            let flags = hex(8)?;
+            let default = fields.first()?.bytes().all(|digit| digit == b'0')
+                && hex(1)? == 0
+                && flags & RTF_UP != 0
+                && flags & RTF_REJECT == 0;
+            default.then(|| Some((hex(5)?, *fields.get(9)?)))?
+        })
+        .min_by_key(|(metric, _)| *metric)
+        .map(|(_, interface)| interface)
+}
+
+#[cfg(test)]
+pub(super) mod tests {
+    use super::*;
+
+    pub const ROUTES: &str = "\
+Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+wlan0\t00000000\t0100A8C0\t0003\t0\t0\t600\t00000000\t0\t0\t0
+enp3s0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+enp3s0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+wlan0\t0000A8C0\t00000000\t0001\t0\t0\t600\t00FFFFFF\t0\t0\t0
+";
+
+    pub const IPV6_ROUTES: &str = "\
+fe800000000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001   enp3s0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003    wlan0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000064 00000001 00000000 00000003   enp3s0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+";
+
+    #[test]
+    fn default_route() {
+        assert_eq!(default_interface(ROUTES), Some("enp3s0"));
+
+        let header = ROUTES.lines().next().unwrap();
+        assert_eq!(default_interface(header), None);
+
+        // a down route doesn't count
+        let down = ROUTES.replacen("0003\t0\t0\t100", "0002\t0\t0\t100", 1);
+        assert_eq!(default_interface(&down), Some("wlan0"));
+    }
+
+    #[test]
+    fn default_route_v6() {
+        assert_eq!(default_interface_v6(IPV6_ROUTES), Some("enp3s0"));
+
+        // only the unreachable route on lo
+        let lo: String = IPV6_ROUTES
+            .lines()
+            .skip(3)
+            .map(|line| format!("{line}\n"))
+            .collect();
+        assert_eq!(default_interface_v6(&lo), None);
+    }
+}
@@ -0,0 +1,242 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::color::Color;
+use crate::component::Fg;
+use crate::component::net::{AUTO, DefaultRoute, NET, NoSuchInterface, PROC_NET};
+use crate::sysfs::SysfsAttr;
+
+/// the link state of a network interface, e.g. enp3s0, or of the interface of the default route with `auto`
+pub struct Network {
+    net: PathBuf,
+    selection: Selection,
+}
+
+enum Selection {
+    Fixed(Link),
+    /// the link is re-opened when the default route moves to another interface
+    Auto {
+        route: DefaultRoute,
+        link: RefCell<Option<Link>>,
+    },
+}
+
+struct Link {
+    name: String,
+    operstate: SysfsAttr,
+    /// Mb/s, not readable while the link is down
+    speed: Option<SysfsAttr>,
+    duplex: Option<SysfsAttr>,
+    carrier_changes: Option<SysfsAttr>,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Status {
+    state: String,
+    speed: Option<u32>,
+    duplex: Option<String>,
+    carrier_changes: Option<u64>,
+}
+
+impl Link {
+    fn open(net: &Path, name: &str) -> Result<Self, NoSuchInterface> {
+        let dir = net.join(name);
+        let operstate = SysfsAttr::open(dir.join("operstate")).map_err(|_| NoSuchInterface)?;
+        let open = |attr| SysfsAttr::open(dir.join(attr)).ok();
+
+        Ok(Self {
+            name: name.to_string(),
+            operstate,
+            speed: open("speed"),
+            duplex: open("duplex"),
+            carrier_changes: open("carrier_changes"),
+        })
+    }
+
+    fn read(&self) -> Status {
+        let string = |attr: &SysfsAttr| {
+            attr.read_with(|value| String::from_utf8_lossy(value).into_owned())
+                .ok()
+        };
+
+        Status {
+            state: string(&self.operstate).unwrap_or_else(|| "?".to_string()),
+            // -1 when unknown, e.g. for virtual interfaces
+            speed: self
+                .speed
+                .as_ref()
+                .and_then(|speed| speed.read::<i64>().ok())
+                .and_then(|speed| u32::try_from(speed).ok()),
+            duplex: self
+                .duplex
+                .as_ref()
+                .and_then(string)
+                .filter(|duplex| duplex != "unknown"),
+            carrier_changes: self
+                .carrier_changes
+                .as_ref()
+                .and_then(|changes| changes.read().ok()),
+        }
+    }
+}
+
+impl Network {
+    pub fn new(interface: &str) -> Result<Self, NoSuchInterface> {
+        Self::in_dir(Path::new(NET), Path::new(PROC_NET), interface)
+    }
+
+    fn in_dir(net: &Path, proc_net: &Path, interface: &str) -> Result<Self, NoSuchInterface> {
+        let selection = if interface == AUTO {
+            Selection::Auto {
+                route: DefaultRoute::open(proc_net).map_err(|_| NoSuchInterface)?,
+                link: RefCell::new(None),
+            }
+        } else {
+            Selection::Fixed(Link::open(net, interface)?)
+        };
+
+        Ok(Self {
+            net: net.to_path_buf(),
+            selection,
+        })
+    }
+
+    /// the interface name (with `auto` only) and its status, None without a default route
+    fn read(&self) -> Option<(Option<String>, Status)> {
+        let (route, link) = match &self.selection {
+            Selection::Fixed(link) => return Some((None, link.read())),
+            Selection::Auto { route, link } => (route, link),
+        };
+
+        let interface = route.interface()?;
+
+        let mut link = link.borrow_mut();
+        if link.as_ref().is_none_or(|link| link.name != interface) {
+            *link = Link::open(&self.net, &interface).ok();
+        }
+
+        let link = link.as_ref()?;
+        Some((Some(link.name.clone()), link.read()))
+    }
+}
+
+impl fmt::Display for Network {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some((name, status)) = self.read() else {
+            return write!(f, "{}-{}", Fg(Color::DIM), Fg(crate::FG));
+        };
+
+        if let Some(name) = name {
+            write!(f, "{name} ")?;
+        }
+
+        write!(f, "{}", status.state)?;
+        if status.state != "up" {
+            return Ok(());
+        }
+
+        if let Some(speed) = status.speed {
+            write!(f, " {speed}M")?;
+        }
+        if let Some(duplex) = status.duplex {
+            write!(f, " {duplex}")?;
+        }
+        if let Some(changes) = status.carrier_changes {
+            write!(f, " ↯{changes}")?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::component::net::tests::{IPV6_ROUTES, ROUTES};
+    use crate::fixture;
+
+    fn sample_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        let net = dir.path().join("net");
+        fixture::attrs(
+            &net.join("enp3s0"),
+            &[
+                ("operstate", "up"),
+                ("speed", "1000"),
+                ("duplex", "full"),
+                ("carrier_changes", "4"),
+            ],
+        );
+        fixture::attrs(
+            &net.join("wlan0"),
+            &[
+                ("operstate", "up"),
+                ("speed", "-1"),
+                ("duplex", "unknown"),
+                ("carrier_changes", "2"),
+            ],
+        );
+        fs::write(dir.path().join("route"), ROUTES).unwrap();
+        fs::write(dir.path().join("ipv6_route"), IPV6_ROUTES).unwrap();
+        dir
+    }
+
+    fn open(dir: &Path, interface: &str) -> Result<Network, NoSuchInterface> {
+        Network::in_dir(&dir.join("net"), dir, interface)
+    }
+
+    #[test]
+    fn ipv6_only() {
+        let dir = sample_tree();
+        let header = ROUTES.lines().next().unwrap();
+        fs::write(dir.path().join("route"), header).unwrap();
+
+        let network = open(dir.path(), "auto").unwrap();
+        assert_eq!(network.to_string(), "enp3s0 up 1000M full ↯4");
+
+        fs::remove_file(dir.path().join("ipv6_route")).unwrap();
+        let network = open(dir.path(), "auto").unwrap();
+        assert_eq!(network.read(), None);
+    }
+
+    #[test]
+    fn fixed() {
+        let dir = sample_tree();
+        let network = open(dir.path(), "wlan0").unwrap();
+
+        assert_eq!(
+            network.read(),
+            Some((
+                None,
+                Status {
+                    state: "up".to_string(),
+                    speed: None,
+                    duplex: None,
+                    carrier_changes: Some(2),
+                }
+            ))
+        );
+        assert_eq!(network.to_string(), "up ↯2");
+        assert!(open(dir.path(), "eth0").is_err());
+    }
+
+    #[test]
+    fn follows_default_route() {
+        let dir = sample_tree();
+        let network = open(dir.path(), "auto").unwrap();
+        assert_eq!(network.to_string(), "enp3s0 up 1000M full ↯4");
+
+        // unplugged
+        let wlan0 = ROUTES.lines().take(2).collect::<Vec<_>>().join("\n");
+        fs::write(dir.path().join("route"), wlan0).unwrap();
+        assert_eq!(network.to_string(), "wlan0 up ↯2");
+
+        let header = ROUTES.lines().next().unwrap();
+        fs::write(dir.path().join("route"), header).unwrap();
+        fs::write(dir.path().join("ipv6_route"), "").unwrap();
+        assert_eq!(network.read(), None);
+    }
+}
@@ -0,0 +1,290 @@
+use std::cell::{Cell, RefCell};
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use std::{fmt, fs, str};
+
+use crate::color::Color;
+use crate::component::{Fg, Sanitized};
+use crate::sysfs::SysfsAttr;
+
+const POWER_SUPPLY: &str = "/sys/class/power_supply";
+
+/// batteries of wireless peripherals (mice, keyboards, headsets), which are only shown when their
+/// battery is low or when they were recently connected
+pub struct Peripherals {
+    dir: PathBuf,
+    devices: RefCell<Vec<Device>>,
+    /// power supplies that aren't peripherals, e.g. AC and BAT0
+    ignored: RefCell<Vec<OsString>>,
+    /// whether the devices connected before startup were picked up
+    scanned: Cell<bool>,
+    low: u8,
+    recent: Duration,
+}
+
+struct Device {
+    name: OsString,
+    model: String,
+    capacity: Option<SysfsAttr>,
+    level: Option<SysfsAttr>,
+    /// None for devices that were already connected at startup, which are never recent
+    connected: Option<Instant>,
+}
+
+impl Device {
+    fn open(path: PathBuf, connected: Option<Instant>) -> Option<Self> {
+        let read = |file| fs::read_to_string(path.join(file)).unwrap_or_default();
+
+        if read("scope").trim() != "Device" {
+            return None;
+        }
+
+        let name = path.file_name()?.to_os_string();
+        // the model name is set by the peripheral itself, e.g. over Bluetooth
+        let model = match read("model_name").trim() {
+            "" => Sanitized(&name.to_string_lossy()).to_string(),
+            model => Sanitized(model).to_string(),
+        };
+        let capacity = SysfsAttr::open(path.join("capacity")).ok();
+        let level = SysfsAttr::open(path.join("capacity_level")).ok();
+
+        Some(Self {
+            name,
+            model,
+            capacity,
+            level,
+            connected,
+        })
+    }
+}
+
+impl Peripherals {
+    /// a device is low when its capacity is at most `low` percents (or when it reports a low
+    /// capacity level), and recent for `recent` after it was connected
+    pub fn new(low: u8, recent: Duration) -> Self {
+        Self {
+            dir: PathBuf::from(POWER_SUPPLY),
+            devices: RefCell::default(),
+            ignored: RefCell::default(),
+            scanned: Cell::new(false),
+            low,
+            recent,
+        }
+    }
+
+    /// picks up devices that were connected, and drops devices that were disconnected
+    fn rescan(&self, devices: &mut Vec<Device>) {
+        let mut ignored = self.ignored.borrow_mut();
+
+        let Ok(entries) = self.dir.read_dir() else {
+            devices.clear();
+            return;
+        };
+
+        let connected = self.scanned.replace(true).then(Instant::now);
+
+        let names: Vec<_> = entries.flatten().map(|entry| entry.file_name()).collect();
+        devices.retain(|device| names.contains(&device.name));
+        ignored.retain(|name| names.contains(name));
+
+        for name in names {
+            if ignored.contains(&name) || devices.iter().any(|device| device.name == name) {
+                continue;
+            }
+
+            match Device::open(self.dir.join(&name), connected) {
+                Some(device) => devices.push(device),
+                None => ignored.push(name),
+            }
+        }
+    }
+
+    /// whether a device is low, or None if it shouldn't be shown
+    fn low(&self, device: &Device, capacity: Option<u8>) -> Option<bool> {
+        let level_low = device
+            .level
+            .as_ref()
+            .and_then(|level| {
+                level
+                    .read_with(|level| matches!(level, b"Low" | b"Critical"))
+                    .ok()
+            })
+            .unwrap_or_default();
+
+        let low = level_low || capacity.is_some_and(|capacity| capacity <= self.low);
+        let recent = device
+            .connected
+            .is_some_and(|connected| connected.elapsed() < self.recent);
+        (low || recent).then_some(low)
+    }
+}
+
+impl fmt::Display for Peripherals {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut devices = self.devices.borrow_mut();
+        self.rescan(&mut devices);
+
+        for device in devices.iter() {
+            let capacity: Option<u8> = device.capacity.as_ref().and_then(|c| c.read().ok());
+            let Some(low) = self.low(device, capacity) else {
+                continue;
+            };
+
+            let color = if low { Color::RED } else { crate::FG };
+            write!(f, "  {} {}", crate::label(&device.model), Fg(color))?;
+
+            if let Some(capacity) = capacity {
+                write!(f, "{}%", capacity)?;
+            } else if let Some(level) = device.level.as_ref() {
+                level
+                    .read_with(|level| write!(f, "{}", str::from_utf8(level).unwrap_or("?")))
+                    .unwrap_or(Ok(()))?;
+            } else {
+                write!(f, "?")?;
+            }
+
+            write!(f, "{}", Fg(crate::FG))?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+    use crate::fixture;
+
+    fn supply(root: &Path, name: &str, scope: Option<&str>, attrs: &[(&str, &str)]) {
+        let scope = scope.map(|scope| ("scope", scope));
+        fixture::attrs(&root.join(name), &[attrs, scope.as_slice()].concat());
+    }
+
+    fn peripherals(dir: &Path) -> Peripherals {
+        Peripherals {
+            dir: dir.to_path_buf(),
+            ..Peripherals::new(15, Duration::from_secs(3600))
+        }
+    }
+
+    /// the model and lowness of the devices that are shown, after a rescan
+    fn shown(peripherals: &Peripherals) -> Vec<(String, bool)> {
+        let mut devices = peripherals.devices.borrow_mut();
+        peripherals.rescan(&mut devices);
+
+        let mut shown: Vec<_> = devices
+            .iter()
+            .filter_map(|device| {
+                let capacity = device.capacity.as_ref().and_then(|c| c.read().ok());
+                let low = peripherals.low(device, capacity)?;
+                Some((device.model.clone(), low))
+            })
+            .collect();
+        shown.sort();
+        shown
+    }
+
+    #[test]
+    fn only_device_scope() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(dir.path(), "AC", None, &[("online", "1")]);
+        supply(dir.path(), "BAT0", Some("System"), &[("capacity", "5")]);
+        supply(
+            dir.path(),
+            "hidpp_battery_0",
+            Some("Device"),
+            &[("model_name", "MX Master 3"), ("capacity", "5")],
+        );
+
+        let peripherals = peripherals(dir.path());
+        assert_eq!(shown(&peripherals), [("MX Master 3".to_string(), true)]);
+        assert_eq!(peripherals.devices.borrow().len(), 1);
+        assert_eq!(peripherals.ignored.borrow().len(), 2);
+    }
+
+    #[test]
+    fn hostile_model_name() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(
+            dir.path(),
+            "hid-00:11:22:33:44:55-battery",
+            Some("Device"),
+            &[
+                ("model_name", "%{F:FF0000}%{A:reboot:}Mouse\x1b[0m"),
+                ("capacity", "5"),
+            ],
+        );
+
+        let peripherals = peripherals(dir.path());
+        assert_eq!(
+            shown(&peripherals),
+            [("{F:FF0000}{A:reboot:}Mouse[0m".to_string(), true)]
+        );
+        assert!(!peripherals.to_string().contains("%{A"));
+    }
+
+    #[test]
+    fn capacity_and_level() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(dir.path(), "mouse", Some("Device"), &[("capacity", "15")]);
+        supply(
+            dir.path(),
+            "keyboard",
+            Some("Device"),
+            &[("capacity", "16")],
+        );
+        supply(
+            dir.path(),
+            "headset",
+            Some("Device"),
+            &[("capacity_level", "Critical")],
+        );
+        supply(
+            dir.path(),
+            "gamepad",
+            Some("Device"),
+            &[("capacity_level", "Normal")],
+        );
+
+        let peripherals = peripherals(dir.path());
+        assert_eq!(
+            shown(&peripherals),
+            [("headset".to_string(), true), ("mouse".to_string(), true)]
+        );
+    }
+
+    #[test]
+    fn connected_after_startup() {
+        let dir = tempfile::tempdir().unwrap();
+        supply(dir.path(), "mouse", Some("Device"), &[("capacity", "80")]);
+
+        // devices that were there at startup weren't just connected
+        let peripherals = peripherals(dir.path());
+        assert_eq!(shown(&peripherals), []);
+
+        supply(
+            dir.path(),
+            "keyboard",
+            Some("Device"),
+            &[("capacity", "90")],
+        );
+        assert_eq!(shown(&peripherals), [("keyboard".to_string(), false)]);
+
+        fs::remove_dir_all(dir.path().join("keyboard")).unwrap();
+        assert_eq!(shown(&peripherals), []);
+        assert_eq!(peripherals.devices.borrow().len(), 1);
+
+        // reconnecting makes it recent again
+        supply(
+            dir.path(),
+            "keyboard",
+            Some("Device"),
+            &[("capacity", "90")],
+        );
+        assert_eq!(shown(&peripherals), [("keyboard".to_string(), false)]);
+    }
+}
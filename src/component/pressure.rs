@@ -0,0 +1,214 @@
+use std::cell::RefCell;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::color::Color;
+use crate::component::temperature::{HOT, WARM};
+use crate::component::{Bg, DisplayExt as _, Fg, USAGE_BG, general_bar, usage_bar};
+use crate::metrics::ProcFile;
+
+const PRESSURE: &str = "/proc/pressure";
+const CGROUP: &str = "/sys/fs/cgroup";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Resource {
+    Cpu,
+    Memory,
+    Io,
+}
+
+impl Resource {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Cpu => "cpu",
+            Self::Memory => "memory",
+            Self::Io => "io",
+        }
+    }
+
+    fn short_name(self) -> &'static str {
+        match self {
+            Self::Cpu => "CPU",
+            Self::Memory => "MEM",
+            Self::Io => "IO",
+        }
+    }
+}
+
+/// Pressure Stall Information, the share of time in which tasks were stalled on a resource
+pub struct Pressure {
+    resources: Vec<(Resource, Option<RefCell<ProcFile>>)>,
+    full: bool,
+    bar: bool,
+    warn: f32,
+    crit: f32,
+}
+
+impl Pressure {
+    /// reads the system-wide pressure, or the pressure of the given cgroup (relative to
+    /// /sys/fs/cgroup) when one is specified.
+    /// `full` shows the share of time in which all tasks were stalled, instead of some tasks.
+    /// resources that can't be read (e.g. when PSI is disabled) are shown as unavailable.
+    pub fn new(
+        resources: &[Resource],
+        cgroup: Option<&str>,
+        full: bool,
+        bar: bool,
+        warn: f32,
+        crit: f32,
+    ) -> Result<Self, NoSuchCgroup> {
+        let path = |resource: Resource| match cgroup {
+            Some(cgroup) => {
+                PathBuf::from(format!("{CGROUP}/{cgroup}/{}.pressure", resource.name()))
+            }
+            None => PathBuf::from(format!("{PRESSURE}/{}", resource.name())),
+        };
+
+        if let Some(cgroup) = cgroup
+            && !PathBuf::from(CGROUP).join(cgroup).is_dir()
+        {
+            return Err(NoSuchCgroup);
+        }
+
+        let resources = resources
+            .iter()
+            .map(|&resource| {
+                let file = ProcFile::open(path(resource)).ok().map(RefCell::new);
+                (resource, file)
+            })
+            .collect();
+
+        Ok(Self {
+            resources,
+            full,
+            bar,
+            warn,
+            crit,
+        })
+    }
+
+    fn color(&self, avg10: f32) -> Color {
+        if avg10 < self.warn {
+            crate::FG
+        } else if avg10 < self.crit {
+            WARM
+        } else {
+            HOT
+        }
+    }
+}
+
+impl fmt::Display for Pressure {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", crate::label("PSI"), Fg(crate::FG))?;
+
+        for (resource, file) in &self.resources {
+            write!(f, " {} ", resource.short_name())?;
+
+            // the `full` line is missing for cpu on older kernels, which isn't the same as `some`
+            let avg10 = file
+                .as_ref()
+                .and_then(|file| read_avg10(&mut file.borrow_mut(), self.full));
+
+            let Some(avg10) = avg10 else {
+                if self.bar {
+                    // a full bar of the same width
+                    let bar = Bg(USAGE_BG).chain(Fg(Color::DIM)).chain(general_bar(1.));
+                    write!(f, "{}{}{}", bar, Fg(crate::FG), Bg(crate::BG))?;
+                } else {
+                    write!(f, "{}{:>3}{}", Fg(Color::DIM), "-", Fg(crate::FG))?;
+                }
+                continue;
+            };
+
+            if self.bar {
+                let bar = Bg(USAGE_BG).chain(usage_bar(avg10 / 100.));
+                write!(f, "{}{}{}", bar, Fg(crate::FG), Bg(crate::BG))?;
+            } else if avg10 < 9.95 {
+                // 9.95 and up would be rounded to 10.0
+                write!(f, "{}{:3.1}{}", Fg(self.color(avg10)), avg10, Fg(crate::FG))?;
+            } else {
+                write!(f, "{}{:3.0}{}", Fg(self.color(avg10)), avg10, Fg(crate::FG))?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+// some avg10=0.00 avg60=0.00 avg300=0.00 total=0
+// full avg10=0.00 avg60=0.00 avg300=0.00 total=0
+fn read_avg10(file: &mut ProcFile, full: bool) -> Option<f32> {
+    let text = file.read().ok()?;
+
+    let kind = if full { "full " } else { "some " };
+    let line = text.lines().find(|line| line.starts_with(kind))?;
+
+    line.split_ascii_whitespace()
+        .find_map(|word| word.strip_prefix("avg10="))?
+        .parse()
+        .ok()
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("no such cgroup")]
+pub struct NoSuchCgroup;
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::Path;
+
+    use super::*;
+    use crate::fixture;
+
+    const CPU: &str = "some avg10=9.97 avg60=4.20 avg300=1.05 total=123456789\n";
+    const MEMORY: &str = "\
+some avg10=0.52 avg60=0.31 avg300=0.08 total=3456789
+full avg10=0.31 avg60=0.12 avg300=0.02 total=1234567
+";
+
+    fn pressure(dir: &Path, full: bool, bar: bool) -> String {
+        let file = |name| ProcFile::open(dir.join(name)).ok().map(RefCell::new);
+        let pressure = Pressure {
+            resources: vec![
+                (Resource::Cpu, file("cpu")),
+                (Resource::Memory, file("memory")),
+                (Resource::Io, file("io")),
+            ],
+            full,
+            bar,
+            warn: 10.,
+            crit: 30.,
+        };
+        pressure.to_string()
+    }
+
+    #[test]
+    fn some_and_full() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("cpu"), CPU).unwrap();
+        fs::write(dir.path().join("memory"), MEMORY).unwrap();
+
+        // rounded up to 10, in the same width. io isn't available
+        let shown = |full| fixture::plain(&pressure(dir.path(), full, false));
+        assert_eq!(shown(false), "PSI CPU  10 MEM 0.5 IO   -");
+
+        // the old kernel has no `full` line for cpu
+        assert_eq!(shown(true), "PSI CPU   - MEM 0.3 IO   -");
+    }
+
+    #[test]
+    fn bar() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("cpu"), CPU).unwrap();
+
+        let shown = pressure(dir.path(), false, true);
+        assert_eq!(fixture::plain(&shown), "PSI CPU  MEM  IO ");
+        assert!(shown.contains(&usage_bar(0.0997).to_string()));
+
+        // unavailable, as wide as the ramp
+        let unavailable = format!("{}{}", Fg(Color::DIM), general_bar(1.));
+        assert_eq!(shown.matches(&unavailable).count(), 2);
+    }
+}
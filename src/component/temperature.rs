@@ -1,93 +1,600 @@
-use std::fmt;
-use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::{fmt, fs};
 
 use crate::color::Color;
-use crate::component::Fg;
+use crate::component::hook::Hooks;
+use crate::component::hwmon::{self, HWMON, SelectError};
+use crate::component::{Bg, DisplayExt as _, Fg, USAGE_BG, usage_bar};
+use crate::sysfs::SysfsAttr;
 
 pub const COOL: Color = Color(0x2fb7c4);
 pub const WARM: Color = Color(0xe0c555);
 pub const HOT: Color = Color::RED;
 
+/// used when the sensor has neither tempN_max nor tempN_crit
+const DEFAULT_HOT: i64 = 70_000;
+
+const THERMAL: &str = "/sys/class/thermal";
+
+#[derive(Debug, Default, Clone, Copy, clap::ValueEnum)]
+pub enum TemperatureUnit {
+    #[default]
+    Celsius,
+    Fahrenheit,
+    Kelvin,
+}
+
+/// temperatures of one or more hwmon chips or thermal zones.
+/// each chip is selected by `NAME[:SENSORS]`, where SENSORS is either a comma separated list of
+/// tempN_label values (e.g. `coretemp:Package id 0` or `k10temp:Tctl,Tccd1`), or `max` for the
+/// hottest sensor of the chip. without SENSORS, the first sensor of the chip is used.
+/// when there's no hwmon chip with that name, the thermal zones with that type are used instead,
+/// where SENSORS selects zones by their directory name (e.g. `cpu-thermal:thermal_zone1`).
+///
+/// the temperature is shown as hot from the sensor's tempN_max (or tempN_crit, or 70°C), as warm
+/// from 20°C below that, and as cool below another 10°C. these can be overridden.
 pub struct Temperature {
-    temp_path: PathBuf,
+    groups: Vec<Group>,
+    hooks: Hooks,
+    unit: TemperatureUnit,
+    /// overrides, in milli-celsius
+    warm: Option<i64>,
+    hot: Option<i64>,
+    /// the temperature at which the bar is empty, in milli-celsius
+    bar_idle: Option<i64>,
 }
 
-impl Temperature {
-    pub fn create(zone: &str) -> Result<Self, ThermalZoneError> {
-        let dir = PathBuf::from("/sys/class/hwmon/");
+/// the sensors of a single chip
+struct Group {
+    sensors: Vec<Sensor>,
+    max: bool,
+}
+
+struct Sensor {
+    input: SysfsAttr,
+    trip_points: TripPoints,
+}
+
+/// in milli-celsius
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct TripPoints {
+    max: Option<i64>,
+    crit: Option<i64>,
+}
 
-        let iter = dir
-            .read_dir()
-            .map_err(|_| ThermalZoneError::NoHwmonDirectory)?;
+impl Sensor {
+    fn open(chip: &Path, index: u32) -> Result<Self, ThermalZoneError> {
+        let input = SysfsAttr::open(chip.join(format!("temp{index}_input")))?;
+        let read = |name| {
+            SysfsAttr::open(chip.join(format!("temp{index}_{name}")))
+                .and_then(|attr| attr.read())
+                .ok()
+                // some drivers report 0 when there's no trip point
+                .filter(|&value: &i64| value > 0)
+        };
 
-        let mut found = None;
+        let trip_points = TripPoints {
+            max: read("max"),
+            crit: read("crit"),
+        };
+
+        Ok(Self { input, trip_points })
+    }
 
-        for entry in iter {
-            let Ok(entry) = entry else { continue };
+    fn open_zone(zone: &Path) -> Result<Self, ThermalZoneError> {
+        let input = SysfsAttr::open(zone.join("temp"))?;
 
-            if !entry.file_name().as_encoded_bytes().starts_with(b"hwmon") {
+        let mut trip_points = TripPoints::default();
+        for index in 0.. {
+            let read = |name| fs::read_to_string(zone.join(format!("trip_point_{index}_{name}")));
+            let (Ok(kind), Ok(temp)) = (read("type"), read("temp")) else {
+                break;
+            };
+
+            let Some(temp) = temp.trim().parse().ok().filter(|&temp: &i64| temp > 0) else {
                 continue;
+            };
+
+            match kind.trim() {
+                "critical" => trip_points.crit = Some(temp),
+                "hot" => trip_points.max = Some(temp),
+                "passive" => trip_points.max = trip_points.max.or(Some(temp)),
+                _ => {}
             }
+        }
 
-            let mut path = entry.path();
-            path.push("name");
+        Ok(Self { input, trip_points })
+    }
+}
 
-            let Ok(name) = fs::read_to_string(&path) else {
-                continue
-            };
+/// where the sensors are searched in
+struct Sources {
+    hwmon: PathBuf,
+    thermal: PathBuf,
+}
+
+/// the thermal zones in `dir` along with their types, sorted by path
+fn thermal_zones(dir: &Path) -> Option<Vec<(String, PathBuf)>> {
+    let mut zones: Vec<_> = dir
+        .read_dir()
+        .ok()?
+        .flatten()
+        .filter(|entry| {
+            let name = entry.file_name();
+            name.as_encoded_bytes().starts_with(b"thermal_zone")
+        })
+        .filter_map(|entry| {
+            let path = entry.path();
+            let kind = fs::read_to_string(path.join("type")).ok()?;
+            Some((kind.trim().to_string(), path))
+        })
+        .collect();
+
+    zones.sort_by(|a, b| a.1.cmp(&b.1));
+    Some(zones)
+}
+
+impl Group {
+    fn open(sources: &Sources, spec: &str) -> Result<Self, ThermalZoneError> {
+        let (name, selection) = match spec.split_once(':') {
+            Some((name, selection)) => (name, Some(selection)),
+            None => (spec, None),
+        };
+
+        let chips = hwmon::chips(&sources.hwmon);
+        let zones = thermal_zones(&sources.thermal);
+        if chips.is_none() && zones.is_none() {
+            return Err(ThermalZoneError::NoSensorDirectory);
+        }
+
+        let chips = chips.unwrap_or_default();
+        let zones = zones.unwrap_or_default();
+
+        if let Some((_, chip)) = chips.iter().find(|(chip_name, _)| chip_name == name) {
+            return Self::open_chip(chip, selection);
+        }
+
+        let matching: Vec<_> = zones
+            .iter()
+            .filter(|(kind, _)| kind == name)
+            .map(|(_, path)| path.as_path())
+            .collect();
+
+        if !matching.is_empty() {
+            return Self::open_zones(&matching, selection);
+        }
 
-            if name.trim() != zone {
-                continue
+        Err(ThermalZoneError::NoSuchThermalZone {
+            name: name.to_string(),
+            available: hwmon::names(chips.iter().chain(&zones)),
+        })
+    }
+
+    fn open_chip(chip: &Path, selection: Option<&str>) -> Result<Self, ThermalZoneError> {
+        let selection = hwmon::select(chip, "temp", selection).map_err(|err| match err {
+            SelectError::Empty => ThermalZoneError::NoSensors,
+            SelectError::NoSuchLabel { label, available } => {
+                ThermalZoneError::NoSuchSensor { label, available }
             }
+        })?;
+
+        let sensors = selection
+            .indices
+            .into_iter()
+            .map(|index| Sensor::open(chip, index))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self {
+            sensors,
+            max: selection.max,
+        })
+    }
 
-            path.pop();
-            path.push("temp1_input");
-            found = Some(path);
-            break
+    fn open_zones(zones: &[&Path], selection: Option<&str>) -> Result<Self, ThermalZoneError> {
+        fn file_name(zone: &Path) -> std::borrow::Cow<'_, str> {
+            zone.file_name().unwrap_or_default().to_string_lossy()
         }
 
-        let Some(temp_path) = found else {
-            return Err(ThermalZoneError::NoSuchThermalZone);
+        let (zones, max) = match selection {
+            None => (zones[..1].to_vec(), false),
+
+            Some("max") => (zones.to_vec(), true),
+
+            Some(names) => {
+                let selected = names
+                    .split(',')
+                    .map(|name| {
+                        zones
+                            .iter()
+                            .copied()
+                            .find(|&zone| file_name(zone) == name.trim())
+                            .ok_or_else(|| ThermalZoneError::NoSuchSensor {
+                                label: name.trim().to_string(),
+                                available: zones
+                                    .iter()
+                                    .map(|zone| file_name(zone))
+                                    .collect::<Vec<_>>()
+                                    .join(", "),
+                            })
+                    })
+                    .collect::<Result<_, _>>()?;
+
+                (selected, false)
+            }
+        };
+
+        let sensors = zones
+            .into_iter()
+            .map(Sensor::open_zone)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Self { sensors, max })
+    }
+
+    /// the temperatures to show, in milli-celsius
+    fn readings(&self) -> impl Iterator<Item = (i64, TripPoints)> {
+        let readings = self
+            .sensors
+            .iter()
+            .filter_map(|sensor| Some((sensor.input.read().ok()?, sensor.trip_points)));
+
+        let (all, max) = if self.max {
+            (None, readings.max_by_key(|(value, _)| *value))
+        } else {
+            (Some(readings), None)
         };
 
-        Ok(Self { temp_path })
+        all.into_iter().flatten().chain(max)
     }
 }
 
-impl fmt::Display for Temperature {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        // milli-celsius
-        let value_mc: u32 = fs::read_to_string(self.temp_path.as_path())
-            .unwrap()
-            .trim()
-            .parse()
-            .unwrap();
+impl Temperature {
+    pub fn create(specs: &[String]) -> Result<Self, ThermalZoneError> {
+        let sources = Sources {
+            hwmon: PathBuf::from(HWMON),
+            thermal: PathBuf::from(THERMAL),
+        };
+
+        Self::open(&sources, specs)
+    }
+
+    fn open(sources: &Sources, specs: &[String]) -> Result<Self, ThermalZoneError> {
+        let groups = specs
+            .iter()
+            .map(|spec| Group::open(sources, spec))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            groups,
+            hooks: Hooks::default(),
+            unit: TemperatureUnit::default(),
+            warm: None,
+            hot: None,
+            bar_idle: None,
+        })
+    }
+
+    pub fn with_unit(mut self, unit: TemperatureUnit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    /// overrides the temperatures (in °C) from which it's shown as warm and as hot
+    pub fn with_thresholds(mut self, warm: Option<f32>, hot: Option<f32>) -> Self {
+        self.warm = warm.map(to_milli);
+        self.hot = hot.map(to_milli);
+        self
+    }
+
+    /// shows a bar instead, which is empty at `idle` °C and full at the critical temperature
+    pub fn with_bar(mut self, idle: f32) -> Self {
+        self.bar_idle = Some(to_milli(idle));
+        self
+    }
+
+    /// `hooks` are fired with the highest shown temperature in °C
+    pub fn with_hooks(mut self, hooks: Hooks) -> Self {
+        self.hooks = hooks;
+        self
+    }
+
+    fn readings(&self) -> impl Iterator<Item = (i64, TripPoints)> {
+        self.groups.iter().flat_map(Group::readings)
+    }
 
-        let temp = value_mc / 1000;
+    fn color(&self, value_mc: i64, trip_points: TripPoints) -> Color {
+        let hot = self
+            .hot
+            .or(trip_points.max)
+            .or(trip_points.crit)
+            .unwrap_or(DEFAULT_HOT);
+        let warm = self.warm.unwrap_or(hot - 20_000);
+        let cool = warm - 10_000;
 
-        let color = if temp < 40 {
+        if value_mc < cool {
             COOL
-        } else if temp < 50 {
+        } else if value_mc < warm {
             crate::FG
-        } else if temp < 70 {
+        } else if value_mc < hot {
             WARM
         } else {
             HOT
-        };
+        }
+    }
+}
+
+impl fmt::Display for Temperature {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut hottest = None;
+
+        for (i, (value_mc, trip_points)) in self.readings().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+
+            hottest = hottest.max(Some(value_mc));
+
+            if let Some(idle) = self.bar_idle {
+                let crit = self
+                    .hot
+                    .or(trip_points.crit)
+                    .or(trip_points.max)
+                    .unwrap_or(DEFAULT_HOT);
+                let usage = (value_mc - idle) as f32 / (crit - idle).max(1) as f32;
+                let bar = Bg(USAGE_BG).chain(usage_bar(usage));
+                write!(f, "{}{}", bar, Bg(crate::BG))?;
+                continue;
+            }
+
+            write!(f, "{}", Fg(self.color(value_mc, trip_points)))?;
+
+            // milli-celsius
+            let celsius = value_mc as f32 / 1000.;
+            match self.unit {
+                TemperatureUnit::Celsius => write!(f, "{:.0}°C", celsius)?,
+                TemperatureUnit::Fahrenheit => write!(f, "{:.0}°F", celsius * 9. / 5. + 32.)?,
+                TemperatureUnit::Kelvin => write!(f, "{:.0}K", celsius + 273.15)?,
+            }
+        }
+
+        if let Some(hottest) = hottest {
+            self.hooks.update(hottest as f32 / 1000.);
+        }
 
-        write!(f, "{}{}°C", Fg(color), temp)
+        Ok(())
     }
 }
 
+fn to_milli(celsius: f32) -> i64 {
+    (celsius * 1000.) as i64
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ThermalZoneError {
-    #[error("no such thermal zone")]
-    NoSuchThermalZone,
+    #[error("no hwmon chip or thermal zone named `{name}`, available: {available}")]
+    NoSuchThermalZone { name: String, available: String },
+
+    #[error("the hwmon chip has no temperature sensors")]
+    NoSensors,
+
+    #[error("no sensor labeled `{label}`, available: {available}")]
+    NoSuchSensor { label: String, available: String },
 
-    #[error("/sys/class/hwmon directory does not exist")]
-    NoHwmonDirectory,
+    #[error("neither /sys/class/hwmon nor /sys/class/thermal exist")]
+    NoSensorDirectory,
 
     #[error(transparent)]
     Io(#[from] std::io::Error),
 }
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::fixture;
+
+    fn chip(root: &Path, hwmon: &str, name: &str, sensors: &[(Option<&str>, i64)]) {
+        fixture::hwmon_chip(&root.join("hwmon"), hwmon, name, "temp", sensors);
+    }
+
+    fn zone(root: &Path, zone: &str, kind: &str, temp: i64, trip_points: &[(&str, i64)]) {
+        let mut attrs = vec![
+            ("type".to_string(), kind.to_string()),
+            ("temp".to_string(), temp.to_string()),
+        ];
+        for (i, (kind, temp)) in trip_points.iter().enumerate() {
+            attrs.push((format!("trip_point_{i}_type"), kind.to_string()));
+            attrs.push((format!("trip_point_{i}_temp"), temp.to_string()));
+        }
+        fixture::attrs(&root.join("thermal").join(zone), &attrs);
+    }
+
+    fn sample_tree() -> tempfile::TempDir {
+        let dir = tempfile::tempdir().unwrap();
+        chip(dir.path(), "hwmon0", "acpitz", &[(None, 27800)]);
+        chip(
+            dir.path(),
+            "hwmon1",
+            "coretemp",
+            &[
+                (Some("Package id 0"), 52000),
+                (Some("Core 0"), 48000),
+                (Some("Core 1"), 61000),
+            ],
+        );
+        chip(
+            dir.path(),
+            "hwmon2",
+            "k10temp",
+            &[(Some("Tctl"), 70250), (Some("Tccd1"), 65500)],
+        );
+        dir
+    }
+
+    fn open(root: &Path, specs: &[&str]) -> Result<Temperature, ThermalZoneError> {
+        let sources = Sources {
+            hwmon: root.join("hwmon"),
+            thermal: root.join("thermal"),
+        };
+
+        let specs: Vec<_> = specs.iter().map(|spec| spec.to_string()).collect();
+        Temperature::open(&sources, &specs)
+    }
+
+    /// the shown temperatures, without the colors
+    fn shown(root: &Path, specs: &[&str]) -> Result<String, ThermalZoneError> {
+        Ok(fixture::plain(&open(root, specs)?.to_string()))
+    }
+
+    #[test]
+    fn first_sensor() {
+        let dir = sample_tree();
+        assert_eq!(shown(dir.path(), &["acpitz"]).unwrap(), "28°C");
+        assert_eq!(shown(dir.path(), &["coretemp"]).unwrap(), "52°C");
+    }
+
+    #[test]
+    fn by_label() {
+        let dir = sample_tree();
+        assert_eq!(shown(dir.path(), &["k10temp:Tctl"]).unwrap(), "70°C");
+        assert_eq!(
+            shown(dir.path(), &["coretemp:Core 1,Package id 0"]).unwrap(),
+            "61°C 52°C"
+        );
+    }
+
+    #[test]
+    fn max_of_chip() {
+        let dir = sample_tree();
+        assert_eq!(shown(dir.path(), &["coretemp:max"]).unwrap(), "61°C");
+    }
+
+    #[test]
+    fn multiple_chips() {
+        let dir = sample_tree();
+        assert_eq!(
+            shown(dir.path(), &["acpitz", "k10temp:Tccd1"]).unwrap(),
+            "28°C 66°C"
+        );
+
+        let temperature = open(dir.path(), &["acpitz", "k10temp:max"]).unwrap();
+        let shown = temperature.to_string();
+        assert_eq!(shown, format!("{}28°C {}70°C", Fg(COOL), Fg(HOT)));
+    }
+
+    #[test]
+    fn missing() {
+        let dir = sample_tree();
+        zone(dir.path(), "thermal_zone0", "cpu-thermal", 45000, &[]);
+
+        let Err(ThermalZoneError::NoSuchThermalZone { available, .. }) =
+            shown(dir.path(), &["nvme"])
+        else {
+            panic!("expected a missing thermal zone");
+        };
+        assert_eq!(available, "acpitz, coretemp, k10temp, cpu-thermal");
+
+        let Err(ThermalZoneError::NoSuchSensor { available, .. }) =
+            shown(dir.path(), &["k10temp:Tdie"])
+        else {
+            panic!("expected a missing sensor");
+        };
+        assert_eq!(available, "Tctl, Tccd1");
+
+        let empty = tempfile::tempdir().unwrap();
+        assert!(matches!(
+            shown(empty.path(), &["acpitz"]),
+            Err(ThermalZoneError::NoSensorDirectory)
+        ));
+    }
+
+    #[test]
+    fn thermal_zones() {
+        let dir = tempfile::tempdir().unwrap();
+        zone(
+            dir.path(),
+            "thermal_zone0",
+            "cpu-thermal",
+            45000,
+            &[("passive", 75000), ("critical", 95000)],
+        );
+        zone(dir.path(), "thermal_zone1", "gpu-thermal", 52000, &[]);
+        zone(dir.path(), "thermal_zone2", "cpu-thermal", 48000, &[]);
+
+        assert_eq!(shown(dir.path(), &["gpu-thermal"]).unwrap(), "52°C");
+        assert_eq!(shown(dir.path(), &["cpu-thermal"]).unwrap(), "45°C");
+        assert_eq!(shown(dir.path(), &["cpu-thermal:max"]).unwrap(), "48°C");
+        assert_eq!(
+            shown(dir.path(), &["cpu-thermal:thermal_zone2"]).unwrap(),
+            "48°C"
+        );
+
+        // cool below 45°C, warm from 55°C, hot from the passive trip point
+        let temperature = open(dir.path(), &["cpu-thermal"]).unwrap();
+        assert_eq!(temperature.to_string(), format!("{}45°C", Fg(crate::FG)));
+        fs::write(dir.path().join("thermal/thermal_zone0/temp"), "60000\n").unwrap();
+        assert_eq!(temperature.to_string(), format!("{}60°C", Fg(WARM)));
+        fs::write(dir.path().join("thermal/thermal_zone0/temp"), "75000\n").unwrap();
+        assert_eq!(temperature.to_string(), format!("{}75°C", Fg(HOT)));
+
+        // hwmon chips take precedence over thermal zones
+        chip(dir.path(), "hwmon0", "cpu-thermal", &[(None, 30000)]);
+        assert_eq!(shown(dir.path(), &["cpu-thermal"]).unwrap(), "30°C");
+    }
+
+    #[test]
+    fn trip_points() {
+        let dir = sample_tree();
+        let chip = dir.path().join("hwmon").join("hwmon3");
+        fixture::attrs(
+            &chip,
+            &[
+                ("name", "nvme"),
+                ("temp1_input", "72850"),
+                ("temp1_max", "84850"),
+                ("temp1_crit", "89850"),
+                ("temp2_input", "40000"),
+                ("temp2_max", "0"),
+            ],
+        );
+
+        // warm from 64.85°C, hot from 84.85°C
+        let temperature = open(dir.path(), &["nvme:max"]).unwrap();
+        assert_eq!(temperature.to_string(), format!("{}73°C", Fg(WARM)));
+
+        // a max of 0 means there's none, so it's hot from 70°C
+        fs::write(chip.join("temp2_input"), "69000\n").unwrap();
+        fs::write(chip.join("temp1_input"), "0\n").unwrap();
+        let temperature = open(dir.path(), &["nvme:max"]).unwrap();
+        assert_eq!(temperature.to_string(), format!("{}69°C", Fg(WARM)));
+
+        // the default thresholds are used for sensors without trip points
+        let temperature = open(dir.path(), &["acpitz"]).unwrap();
+        assert_eq!(temperature.to_string(), format!("{}28°C", Fg(COOL)));
+
+        let temperature = temperature.with_thresholds(None, Some(27.));
+        assert_eq!(temperature.to_string(), format!("{}28°C", Fg(HOT)));
+    }
+
+    #[test]
+    fn units() {
+        let dir = sample_tree();
+
+        // 70.25°C
+        let temperature = open(dir.path(), &["k10temp:Tctl"]).unwrap();
+        let fahrenheit = temperature.with_unit(TemperatureUnit::Fahrenheit);
+        assert_eq!(fahrenheit.to_string(), format!("{}158°F", Fg(HOT)));
+
+        let temperature = open(dir.path(), &["k10temp:Tctl"]).unwrap();
+        let kelvin = temperature.with_unit(TemperatureUnit::Kelvin);
+        assert_eq!(kelvin.to_string(), format!("{}343K", Fg(HOT)));
+
+        // all units round the same way
+        let temperature = open(dir.path(), &["coretemp:Package id 0"]).unwrap();
+        fs::write(dir.path().join("hwmon/hwmon1/temp1_input"), "52600\n").unwrap();
+        assert_eq!(fixture::plain(&temperature.to_string()), "53°C");
+        let temperature = temperature.with_unit(TemperatureUnit::Kelvin);
+        assert_eq!(fixture::plain(&temperature.to_string()), "326K");
+    }
+}
@@ -0,0 +1,241 @@
+use std::cell::{Cell, RefCell};
+use std::collections::VecDeque;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use crate::component::net::{NET, NoSuchInterface};
+use crate::component::{Bg, ByteSize, Fg, USAGE_BG, spark_bar};
+use crate::sysfs::{self, SysfsAttr};
+
+/// every physical interface
+const ALL: &str = "all";
+
+/// how many rates the sparklines show
+const HISTORY: usize = 8;
+
+/// the smallest scale of a sparkline, so that background chatter doesn't fill it up
+const MIN_SCALE: u64 = 16 * 1024;
+
+/// the download and upload rates of an interface, e.g. wlan0, or of every physical interface with `all`
+pub struct Throughput {
+    source: Source,
+    last: Cell<Option<(Instant, Bytes)>>,
+    sparklines: Option<RefCell<Sparklines>>,
+}
+
+enum Source {
+    Interface {
+        rx: SysfsAttr,
+        tx: SysfsAttr,
+    },
+    /// rescanned every tick, as interfaces come and go
+    All(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Bytes {
+    rx: u64,
+    tx: u64,
+}
+
+#[derive(Default)]
+struct Sparklines {
+    rx: VecDeque<u64>,
+    tx: VecDeque<u64>,
+}
+
+impl Throughput {
+    /// `sparklines` also shows the last rates as bars
+    pub fn new(interface: &str, sparklines: bool) -> Result<Self, NoSuchInterface> {
+        Self::in_dir(Path::new(NET), interface, sparklines)
+    }
+
+    fn in_dir(dir: &Path, interface: &str, sparklines: bool) -> Result<Self, NoSuchInterface> {
+        let source = if interface == ALL {
+            Source::All(dir.to_path_buf())
+        } else {
+            let statistics = dir.join(interface).join("statistics");
+            let open = |name| SysfsAttr::open(statistics.join(name)).map_err(|_| NoSuchInterface);
+            Source::Interface {
+                rx: open("rx_bytes")?,
+                tx: open("tx_bytes")?,
+            }
+        };
+
+        Ok(Self {
+            source,
+            last: Cell::new(None),
+            sparklines: sparklines.then(|| RefCell::new(Sparklines::default())),
+        })
+    }
+
+    fn read(&self) -> Bytes {
+        match &self.source {
+            Source::Interface { rx, tx } => Bytes {
+                rx: rx.read().unwrap_or(0),
+                tx: tx.read().unwrap_or(0),
+            },
+            Source::All(dir) => {
+                let read = |path: PathBuf| {
+                    fs::read(path)
+                        .ok()
+                        .and_then(|bytes| sysfs::parse::<u64>(&bytes))
+                        .unwrap_or(0)
+                };
+
+                dir.read_dir()
+                    .into_iter()
+                    .flatten()
+                    .flatten()
+                    // virtual interfaces (lo, bridges, VPNs) would count traffic twice
+                    .filter(|entry| entry.path().join("device").exists())
+                    .fold(Bytes::default(), |total, entry| {
+                        let statistics = entry.path().join("statistics");
+                        Bytes {
+                            rx: total.rx + read(statistics.join("rx_bytes")),
+                            tx: total.tx + read(statistics.join("tx_bytes")),
+                        }
+                    })
+            }
+        }
+    }
+
+    /// bytes per second since the previous sample, 0 on the first one
+    fn sample(&self, now: Instant) -> Bytes {
+        let bytes = self.read();
+        let Some((then, last)) = self.last.replace(Some((now, bytes))) else {
+            return Bytes::default();
+        };
+
+        let elapsed = now.duration_since(then).as_secs_f64();
+        if elapsed <= 0. {
+            return Bytes::default();
+        }
+
+        // counters reset when a driver is reloaded, or an interface of `all` disappears
+        let rate = |now: u64, last: u64| (now.saturating_sub(last) as f64 / elapsed) as u64;
+        Bytes {
+            rx: rate(bytes.rx, last.rx),
+            tx: rate(bytes.tx, last.tx),
+        }
+    }
+}
+
+fn push(history: &mut VecDeque<u64>, rate: u64) {
+    if history.len() == HISTORY {
+        history.pop_front();
+    }
+    history.push_back(rate);
+}
+
+/// the rates as bars, relative to the highest one
+struct Sparkline<'a>(&'a VecDeque<u64>);
+
+impl fmt::Display for Sparkline<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let history = self.0;
+        let scale = history.iter().copied().max().unwrap_or(0).max(MIN_SCALE);
+
+        write!(f, "{}", Bg(USAGE_BG))?;
+        let padding = (0..HISTORY - history.len()).map(|_| 0);
+        for rate in padding.chain(history.iter().copied()) {
+            write!(f, "{}", spark_bar(rate as f32 / scale as f32))?;
+        }
+        write!(f, "{}{}", Fg(crate::FG), Bg(crate::BG))
+    }
+}
+
+impl fmt::Display for Throughput {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rate = self.sample(Instant::now());
+
+        let Some(sparklines) = &self.sparklines else {
+            return write!(f, "{}↓ {}↑", ByteSize(rate.rx), ByteSize(rate.tx));
+        };
+
+        let mut sparklines = sparklines.borrow_mut();
+        push(&mut sparklines.rx, rate.rx);
+        push(&mut sparklines.tx, rate.tx);
+
+        write!(
+            f,
+            "{} {}↓ {} {}↑",
+            Sparkline(&sparklines.rx),
+            ByteSize(rate.rx),
+            Sparkline(&sparklines.tx),
+            ByteSize(rate.tx)
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::fixture;
+
+    fn interface(net: &Path, name: &str, physical: bool, rx: u64, tx: u64) {
+        let statistics = net.join(name).join("statistics");
+        fixture::attrs(&statistics, &[("rx_bytes", rx), ("tx_bytes", tx)]);
+        if physical {
+            fs::create_dir_all(net.join(name).join("device")).unwrap();
+        }
+    }
+
+    #[test]
+    fn rates() {
+        let start = Instant::now();
+        let dir = tempfile::tempdir().unwrap();
+        interface(dir.path(), "wlan0", true, 1000, 500);
+
+        let throughput = Throughput::in_dir(dir.path(), "wlan0", false).unwrap();
+        assert_eq!(throughput.sample(start), Bytes::default());
+
+        interface(dir.path(), "wlan0", true, 1000 + 24 * 1024, 500 + 512);
+        let rate = throughput.sample(start + Duration::from_secs(2));
+        assert_eq!(
+            rate,
+            Bytes {
+                rx: 12 * 1024,
+                tx: 256
+            }
+        );
+
+        // the driver was reloaded
+        interface(dir.path(), "wlan0", true, 100, 100);
+        let rate = throughput.sample(start + Duration::from_secs(3));
+        assert_eq!(rate, Bytes::default());
+
+        assert!(Throughput::in_dir(dir.path(), "eth0", false).is_err());
+    }
+
+    #[test]
+    fn all_physical() {
+        let start = Instant::now();
+        let dir = tempfile::tempdir().unwrap();
+        interface(dir.path(), "lo", false, 0, 0);
+        interface(dir.path(), "wlan0", true, 0, 0);
+        interface(dir.path(), "enp3s0", true, 0, 0);
+
+        let throughput = Throughput::in_dir(dir.path(), "all", false).unwrap();
+        throughput.sample(start);
+
+        interface(dir.path(), "lo", false, 5000, 5000);
+        interface(dir.path(), "wlan0", true, 1000, 100);
+        interface(dir.path(), "enp3s0", true, 2000, 200);
+        let rate = throughput.sample(start + Duration::from_secs(1));
+        assert_eq!(rate, Bytes { rx: 3000, tx: 300 });
+    }
+
+    #[test]
+    fn sparkline_history() {
+        let mut history = VecDeque::new();
+        for rate in 0..10 {
+            push(&mut history, rate);
+        }
+        assert_eq!(history, (2..10).collect::<VecDeque<_>>());
+    }
+}
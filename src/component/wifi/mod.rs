@@ -0,0 +1,111 @@
+use std::cell::RefCell;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use std::{fmt, fs, str};
+
+use crate::component::Sanitized;
+use crate::component::net::{NET, NoSuchInterface};
+use crate::metrics::ProcFile;
+use crate::sysfs::{self, SysfsAttr};
+
+mod nl80211;
+mod wireless;
+
+use nl80211::{Link, Nl80211};
+
+const WIRELESS: &str = "/proc/net/wireless";
+
+/// how long the link from nl80211 is shown before asking again
+const LINK_INTERVAL: Duration = Duration::from_secs(6);
+
+pub struct Wifi<'a> {
+    interface: &'a str,
+    state: SysfsAttr,
+    /// None if nl80211 isn't available, then the signal comes from /proc/net/wireless
+    nl80211: Option<(Nl80211, u32)>,
+    /// the last link from nl80211 and when it was asked for, None if the request failed
+    link: RefCell<Option<(Instant, Option<Link>)>>,
+    wireless: Option<RefCell<ProcFile>>,
+}
+
+impl<'a> Wifi<'a> {
+    pub fn new(interface: &'a str) -> Result<Self, NoSuchInterface> {
+        let dir = Path::new(NET).join(interface);
+        let state = SysfsAttr::open(dir.join("operstate")).map_err(|_| NoSuchInterface)?;
+
+        let ifindex = fs::read(dir.join("ifindex"))
+            .ok()
+            .and_then(|ifindex| sysfs::parse(&ifindex));
+        let nl80211 = ifindex.and_then(|ifindex| Some((Nl80211::open().ok()?, ifindex)));
+
+        let wireless = ProcFile::open(WIRELESS).ok().map(RefCell::new);
+
+        Ok(Self {
+            interface,
+            state,
+            nl80211,
+            link: RefCell::new(None),
+            wireless,
+        })
+    }
+
+    fn link(&self) -> Option<Link> {
+        let (nl80211, ifindex) = self.nl80211.as_ref()?;
+
+        let now = Instant::now();
+        let mut cached = self.link.borrow_mut();
+        let fresh = cached
+            .as_ref()
+            .is_some_and(|(then, _)| now.duration_since(*then) < LINK_INTERVAL);
+        if !fresh {
+            *cached = Some((now, nl80211.link(*ifindex).ok()));
+        }
+
+        cached.as_ref()?.1.clone()
+    }
+
+    /// the signal quality in percent, from /proc/net/wireless
+    fn quality(&self) -> Option<u8> {
+        let mut file = self.wireless.as_ref()?.borrow_mut();
+        wireless::parse(file.read().ok()?)
+            .into_iter()
+            .find(|row| row.interface == self.interface)
+            .map(|row| row.quality())
+    }
+}
+
+impl fmt::Display for Link {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ssid) = &self.ssid {
+            write!(f, " {}", Sanitized(ssid))?;
+        }
+        if let Some(signal) = self.signal {
+            write!(f, " {signal:3}dBm")?;
+        }
+        if let Some(bitrate) = self.bitrate {
+            write!(f, " {:4}Mb/s", bitrate / 10)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for Wifi<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.state
+            .read_with(|state| {
+                let state = str::from_utf8(state).unwrap_or("?");
+
+                if state == "down" {
+                    return write!(f, "{}", state);
+                }
+
+                if let Some(link) = self.link() {
+                    return write!(f, "{}{}", state, link);
+                }
+
+                let quality = self.quality().unwrap_or_default();
+                write!(f, "{} {:3}", state, quality)
+            })
+            .unwrap()
+    }
+}
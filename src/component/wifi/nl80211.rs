@@ -0,0 +1,235 @@
+use std::io;
+
+use crate::netlink::{self, Message, NLM_F_DUMP, NLM_F_REQUEST, Netlink};
+
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_CMD_GET_STATION: u8 = 17;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_STA_INFO: u16 = 21;
+const NL80211_ATTR_SSID: u16 = 52;
+const NL80211_STA_INFO_SIGNAL: u16 = 7;
+const NL80211_STA_INFO_TX_BITRATE: u16 = 8;
+const NL80211_RATE_INFO_BITRATE: u16 = 1;
+const NL80211_RATE_INFO_BITRATE32: u16 = 5;
+
+/// genlmsghdr, with the command and version 1
+fn genl_header(cmd: u8) -> [u8; 4] {
+    [cmd, 1, 0, 0]
+}
+
+/// the payloads without their genlmsghdr
+fn genl_payloads(payloads: Vec<Vec<u8>>) -> impl Iterator<Item = Vec<u8>> {
+    payloads
+        .into_iter()
+        .filter(|payload| payload.len() >= 4)
+        .map(|payload| payload[4..].to_vec())
+}
+
+/// the connection of a wireless interface
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Link {
+    pub ssid: Option<String>,
+    /// dBm
+    pub signal: Option<i8>,
+    /// 100 kbit/s
+    pub bitrate: Option<u32>,
+}
+
+/// a generic netlink socket with the nl80211 family resolved
+pub struct Nl80211 {
+    socket: Netlink,
+    family: u16,
+}
+
+impl Nl80211 {
+    pub fn open() -> io::Result<Self> {
+        Self::with_socket(Netlink::open(libc::NETLINK_GENERIC)?)
+    }
+
+    fn with_socket(socket: Netlink) -> io::Result<Self> {
+        let request = Message::new(GENL_ID_CTRL, NLM_F_REQUEST)
+            .push(&genl_header(CTRL_CMD_GETFAMILY))
+            .attr(CTRL_ATTR_FAMILY_NAME, b"nl80211\0");
+
+        let family = genl_payloads(socket.request(request)?)
+            .find_map(|payload| {
+                netlink::attrs(&payload)
+                    .find(|(kind, _)| *kind == CTRL_ATTR_FAMILY_ID)
+                    .and_then(|(_, id)| Some(u16::from_ne_bytes(id.try_into().ok()?)))
+            })
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no nl80211 family"))?;
+
+        Ok(Self { socket, family })
+    }
+
+    pub fn link(&self, ifindex: u32) -> io::Result<Link> {
+        let mut link = Link::default();
+
+        let request = Message::new(self.family, NLM_F_REQUEST)
+            .push(&genl_header(NL80211_CMD_GET_INTERFACE))
+            .attr(NL80211_ATTR_IFINDEX, &ifindex.to_ne_bytes());
+        for payload in genl_payloads(self.socket.request(request)?) {
+            if let Some((_, ssid)) =
+                netlink::attrs(&payload).find(|(kind, _)| *kind == NL80211_ATTR_SSID)
+            {
+                link.ssid = Some(String::from_utf8_lossy(ssid).into_owned());
+            }
+        }
+
+        // the access point is the only station of a managed interface
+        let request = Message::new(self.family, NLM_F_REQUEST | NLM_F_DUMP)
+            .push(&genl_header(NL80211_CMD_GET_STATION))
+            .attr(NL80211_ATTR_IFINDEX, &ifindex.to_ne_bytes());
+        for payload in genl_payloads(self.socket.request(request)?) {
+            let Some((_, info)) =
+                netlink::attrs(&payload).find(|(kind, _)| *kind == NL80211_ATTR_STA_INFO)
+            else {
+                continue;
+            };
+
+            for (kind, value) in netlink::attrs(info) {
+                match kind {
+                    NL80211_STA_INFO_SIGNAL => {
+                        link.signal = value.first().map(|&signal| signal as i8);
+                    }
+                    NL80211_STA_INFO_TX_BITRATE => link.bitrate = bitrate(value),
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(link)
+    }
+}
+
+/// the 32-bit bitrate if present, since the 16-bit one saturates above 6.5 Gbit/s
+fn bitrate(rate_info: &[u8]) -> Option<u32> {
+    let mut bitrate = None;
+    for (kind, value) in netlink::attrs(rate_info) {
+        match kind {
+            NL80211_RATE_INFO_BITRATE32 => return Some(u32::from_ne_bytes(value.try_into().ok()?)),
+            NL80211_RATE_INFO_BITRATE => {
+                bitrate = value
+                    .try_into()
+                    .ok()
+                    .map(|value| u16::from_ne_bytes(value) as u32);
+            }
+            _ => {}
+        }
+    }
+    bitrate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::Responder;
+    use crate::netlink::{Header, NLM_F_MULTI, NLMSG_DONE};
+
+    const FAMILY: u16 = 0x1c;
+    const IFINDEX: u32 = 3;
+
+    /// the body of a nested attribute
+    fn nested(attrs: &[(u16, &[u8])]) -> Vec<u8> {
+        let message = attrs
+            .iter()
+            .fold(Message::new(0, 0), |message, (kind, value)| {
+                message.attr(*kind, value)
+            })
+            .answer(0);
+        message[16..].to_vec()
+    }
+
+    /// answers like the kernel for an interface that is connected to `ssid`, if any
+    fn answer(header: Header, payload: &[u8], ssid: Option<&[u8]>) -> Vec<u8> {
+        let ifindex = netlink::attrs(&payload[4..])
+            .find(|(kind, _)| *kind == NL80211_ATTR_IFINDEX)
+            .map(|(_, value)| u32::from_ne_bytes(value.try_into().unwrap()));
+
+        match (header.kind, payload[0]) {
+            (GENL_ID_CTRL, CTRL_CMD_GETFAMILY) => Message::new(GENL_ID_CTRL, 0)
+                .push(&genl_header(1))
+                .attr(CTRL_ATTR_FAMILY_NAME, b"nl80211\0")
+                .attr(CTRL_ATTR_FAMILY_ID, &FAMILY.to_ne_bytes())
+                .answer(header.seq),
+
+            (FAMILY, NL80211_CMD_GET_INTERFACE) => {
+                assert_eq!(ifindex, Some(IFINDEX));
+                let mut message = Message::new(FAMILY, 0)
+                    .push(&genl_header(7))
+                    .attr(NL80211_ATTR_IFINDEX, &IFINDEX.to_ne_bytes());
+                if let Some(ssid) = ssid {
+                    message = message.attr(NL80211_ATTR_SSID, ssid);
+                }
+                message.answer(header.seq)
+            }
+
+            (FAMILY, NL80211_CMD_GET_STATION) => {
+                let mut answer = Vec::new();
+                if ssid.is_some() {
+                    let rate = nested(&[
+                        (NL80211_RATE_INFO_BITRATE, &8667u16.to_ne_bytes()),
+                        (NL80211_RATE_INFO_BITRATE32, &8667u32.to_ne_bytes()),
+                    ]);
+                    let info = nested(&[
+                        (NL80211_STA_INFO_SIGNAL, &[(-52i8) as u8]),
+                        (NL80211_STA_INFO_TX_BITRATE, &rate),
+                    ]);
+                    answer = Message::new(FAMILY, NLM_F_MULTI)
+                        .push(&genl_header(NL80211_CMD_GET_STATION))
+                        .attr(NL80211_ATTR_STA_INFO | 0x8000, &info)
+                        .answer(header.seq);
+                }
+                answer.extend(Message::new(NLMSG_DONE, NLM_F_MULTI).answer(header.seq));
+                answer
+            }
+
+            other => panic!("unexpected request {other:?}"),
+        }
+    }
+
+    fn connect(ssid: Option<&'static [u8]>) -> (Nl80211, Responder) {
+        let (socket, responder) =
+            Responder::spawn(move |header, payload| answer(header, payload, ssid));
+        let nl80211 = Nl80211::with_socket(socket).unwrap();
+        assert_eq!(nl80211.family, FAMILY);
+        (nl80211, responder)
+    }
+
+    #[test]
+    fn connected() {
+        let (nl80211, responder) = connect(Some(b"home"));
+
+        assert_eq!(
+            nl80211.link(IFINDEX).unwrap(),
+            Link {
+                ssid: Some("home".to_string()),
+                signal: Some(-52),
+                bitrate: Some(8667),
+            }
+        );
+
+        responder.stop();
+    }
+
+    #[test]
+    fn disconnected() {
+        let (nl80211, responder) = connect(None);
+
+        assert_eq!(nl80211.link(IFINDEX).unwrap(), Link::default());
+
+        responder.stop();
+    }
+
+    #[test]
+    fn bitrates() {
+        let rate = nested(&[(NL80211_RATE_INFO_BITRATE, &1300u16.to_ne_bytes())]);
+        assert_eq!(bitrate(&rate), Some(1300));
+        assert_eq!(bitrate(&[]), None);
+    }
+}
@@ -0,0 +1,163 @@
+/// a row of /proc/net/wireless
+#[derive(Debug, Clone, PartialEq)]
+pub struct Wireless {
+    pub interface: String,
+    /// device dependent status flags, in hex
+    pub status: u16,
+    /// link quality, often out of 70
+    pub link: f32,
+    /// signal level, in dBm or relative like the link quality, see [`Wireless::dbm`]
+    pub level: f32,
+    pub noise: f32,
+    pub discarded: Discarded,
+    /// missed beacons
+    pub missed: u64,
+}
+
+/// discarded packet counters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Discarded {
+    /// wrong network id
+    pub nwid: u64,
+    /// unable to decrypt
+    pub crypt: u64,
+    /// unable to reassemble
+    pub frag: u64,
+    /// max retries reached
+    pub retry: u64,
+    pub misc: u64,
+}
+
+// levels of at most this are in dBm when not negative, since some drivers report them as unsigned 8-bit
+// (e.g. 201 for -55 dBm), see iw_print_stats in wireless-tools
+const MAX_RELATIVE: f32 = 63.;
+
+impl Wireless {
+    /// the signal level in dBm, None if the driver reports a relative level
+    pub fn dbm(&self) -> Option<f32> {
+        if self.level < 0. {
+            Some(self.level)
+        } else if self.level > MAX_RELATIVE && self.level <= 255. {
+            Some(self.level - 256.)
+        } else {
+            None
+        }
+    }
+
+    /// the signal quality in percent
+    pub fn quality(&self) -> u8 {
+        let quality = match self.dbm() {
+            // source: https://codeberg.org/dnkl/yambar/src/commit/abeffbd9a9fd0b2133343e1149e65d4a795a43d0/modules/network.c#L209
+            Some(dbm) => 2. * (dbm + 100.),
+            None => self.link / 70. * 100.,
+        };
+        quality.clamp(0., 100.) as u8
+    }
+
+    /// None for the header lines, or lines with missing columns
+    fn parse(line: &str) -> Option<Self> {
+        let (interface, rest) = line.split_once(':')?;
+        let mut fields = rest.split_ascii_whitespace();
+
+        let status = u16::from_str_radix(fields.next()?, 16).ok()?;
+
+        // the quality values end with a `.` when they were updated since the last read
+        let mut quality = || -> Option<f32> { fields.next()?.trim_end_matches('.').parse().ok() };
+
+        let link = quality()?;
+        let level = quality()?;
+        let noise = quality()?;
+
+        let mut counter = || -> Option<u64> { fields.next()?.parse().ok() };
+        let discarded = Discarded {
+            nwid: counter()?,
+            crypt: counter()?,
+            frag: counter()?,
+            retry: counter()?,
+            misc: counter()?,
+        };
+        let missed = counter()?;
+
+        Some(Self {
+            interface: interface.trim().to_string(),
+            status,
+            link,
+            level,
+            noise,
+            discarded,
+            missed,
+        })
+    }
+}
+
+/// the rows of /proc/net/wireless, after its 2 header lines
+pub fn parse(table: &str) -> Vec<Wireless> {
+    table.lines().skip(2).filter_map(Wireless::parse).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const IWLWIFI: &str = include_str!("testdata/iwlwifi");
+    // at the edge of its range
+    const ATH9K: &str = include_str!("testdata/ath9k");
+    // an older driver reporting the level as unsigned 8-bit dBm, and one with a relative level
+    const UNSIGNED: &str = include_str!("testdata/unsigned");
+
+    #[test]
+    fn iwlwifi() {
+        let rows = parse(IWLWIFI);
+        assert_eq!(
+            rows,
+            [Wireless {
+                interface: "wlp0s20f3".to_string(),
+                status: 0,
+                link: 61.,
+                level: -49.,
+                noise: -256.,
+                discarded: Discarded {
+                    retry: 3,
+                    misc: 108,
+                    ..Discarded::default()
+                },
+                missed: 0,
+            }]
+        );
+        assert_eq!(rows[0].dbm(), Some(-49.));
+        assert_eq!(rows[0].quality(), 100);
+    }
+
+    #[test]
+    fn weak_signal() {
+        let rows = parse(ATH9K);
+        assert_eq!(rows[0].interface, "wlp3s0");
+        assert_eq!(rows[0].link, 10.);
+        assert_eq!(rows[0].dbm(), Some(-100.));
+        assert_eq!(rows[0].quality(), 0);
+        assert_eq!(rows[0].discarded.retry, 41);
+    }
+
+    #[test]
+    fn unsigned_and_relative_levels() {
+        let rows = parse(UNSIGNED);
+
+        assert_eq!(rows[0].interface, "eth1");
+        assert_eq!(rows[0].dbm(), Some(-55.));
+        assert_eq!(rows[0].quality(), 90);
+
+        assert_eq!(rows[1].interface, "wlan1");
+        assert_eq!(rows[1].status, 4);
+        assert_eq!(rows[1].dbm(), None);
+        assert_eq!(rows[1].quality(), 71);
+        assert_eq!(rows[1].discarded.nwid, 5);
+        assert_eq!(rows[1].missed, 4);
+    }
+
+    #[test]
+    fn malformed() {
+        assert!(parse("").is_empty());
+        assert!(parse(&IWLWIFI[..IWLWIFI.len() - 20]).is_empty());
+        assert_eq!(parse(&UNSIGNED[..UNSIGNED.len() - 20]).len(), 1);
+    }
+}
@@ -1,20 +1,199 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
+
+use crate::component::{GpuField, Hook, IpVersion, MemoryFormat, Resource, TemperatureUnit};
 
 #[derive(Parser)]
 pub struct Config {
-    /// which battery to use, e.g. BAT1 (in /sys/class/power_supply)
-    #[arg(short = 'b', long = "bat")]
-    pub battery: Option<String>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// which batteries to use, e.g. BAT0,BAT1 (in /sys/class/power_supply), or `all` for every battery that powers the system
+    #[arg(short = 'b', long = "bat", value_delimiter = ',', global = true)]
+    pub battery: Vec<String>,
+
+    /// show the capacity of each battery too, when there are multiple batteries
+    #[arg(long = "bat-each")]
+    pub battery_each: bool,
+
+    /// show the battery health, cycle count and charge thresholds
+    #[arg(long = "bat-detail")]
+    pub battery_detail: bool,
+
+    /// show whether the system runs on AC, and the power draw of the batteries
+    #[arg(long = "power")]
+    pub power: bool,
+
+    /// power draw in watts from which it's shown as warm
+    #[arg(long = "power-warn", default_value_t = 15.)]
+    pub power_warn: f32,
 
-    /// which GPU to use, e.g. card0 (in /sys/class/drm, depends on drm/{gpu}/device/gpu_busy_percent)
+    /// power draw in watts from which it's shown as hot
+    #[arg(long = "power-crit", default_value_t = 30.)]
+    pub power_crit: f32,
+
+    /// show the batteries of wireless peripherals (mice, keyboards, headsets) when low or recently connected
+    #[arg(long = "peripherals")]
+    pub peripherals: bool,
+
+    /// capacity percentage at which a peripheral battery is considered low
+    #[arg(long = "peripherals-low", default_value_t = 20)]
+    pub peripherals_low: u8,
+
+    /// for how many seconds a newly connected peripheral is shown
+    #[arg(long = "peripherals-recent", default_value_t = 60)]
+    pub peripherals_recent: u64,
+
+    /// which GPUs to use: a card, e.g. card0 (in /sys/class/drm, depends on drm/{gpu}/device/gpu_busy_percent,
+    /// or the fdinfo of the processes for Intel cards), PCI ids, e.g. 1002:73df or 1002, a driver, e.g. amdgpu,
+    /// or `auto` for every card with usage data
     #[arg(short = 'g', long = "gpu")]
     pub gpu: Option<String>,
 
-    /// which thermal component to use, e.g. acpitz (in /sys/class/hwmon/, and each hwmonX has /name)
+    /// what to show next to the GPU usage, e.g. vram,temp,power,clock (AMD cards, in drm/{gpu}/device)
+    #[arg(long = "gpu-fields", value_enum, value_delimiter = ',')]
+    pub gpu_fields: Vec<GpuField>,
+
+    /// show the process that uses the GPU the most, from the DRM fdinfo in /proc
+    #[arg(long = "gpu-top")]
+    pub gpu_top: bool,
+
+    /// how many seconds the GPU usage of the processes is averaged over
+    #[arg(long = "gpu-top-interval", default_value_t = 5)]
+    pub gpu_top_interval: u64,
+
+    /// which thermal component to use, e.g. acpitz (in /sys/class/hwmon/, and each hwmonX has /name), or a thermal zone type, e.g. cpu-thermal (in /sys/class/thermal/, and each thermal_zoneX has /type).
+    /// specific sensors can be selected by their tempN_label, e.g. `coretemp:Package id 0` or `k10temp:Tctl,Tccd1`, or `coretemp:max` for the hottest one.
+    /// can be given multiple times
     #[arg(short = 't', long = "thermal")]
-    pub thermal: Option<String>,
+    pub thermal: Vec<String>,
+
+    /// the unit to show the temperature in
+    #[arg(long = "thermal-unit", value_enum, default_value_t)]
+    pub thermal_unit: TemperatureUnit,
+
+    /// temperature in °C from which it's shown as warm (by default, 20°C below the hot temperature)
+    #[arg(long = "thermal-warm")]
+    pub thermal_warm: Option<f32>,
+
+    /// temperature in °C from which it's shown as hot (by default, the sensor's tempN_max or tempN_crit, or 70°C)
+    #[arg(long = "thermal-hot")]
+    pub thermal_hot: Option<f32>,
+
+    /// show the temperature as a bar, which is full at the sensor's critical temperature
+    #[arg(long = "thermal-bar")]
+    pub thermal_bar: bool,
+
+    /// temperature in °C at which the temperature bar is empty
+    #[arg(long = "thermal-idle", default_value_t = 30.)]
+    pub thermal_idle: f32,
+
+    /// which fans to show, from a hwmon chip, e.g. thinkpad (in /sys/class/hwmon/, and each hwmonX has /name).
+    /// specific fans can be selected by their fanN_label, e.g. `dell_smm:Processor Fan`, or `thinkpad:max` for the fastest one.
+    /// `loudest` shows the fastest fan across all of the chips
+    #[arg(long = "fan")]
+    pub fan: Option<String>,
+
+    /// show the duty cycle of the fans (from pwmN) as a bar too
+    #[arg(long = "fan-bar")]
+    pub fan_bar: bool,
+
+    /// which network interface to show the link state, speed and duplex of, e.g. enp3s0 (in /sys/class/net),
+    /// or `auto` for the interface of the default route
+    #[arg(long = "link")]
+    pub link: Option<String>,
+
+    /// which network interface to show the IP addresses of, e.g. wlan0 (in /sys/class/net), or `auto` for the
+    /// interface of the default route
+    #[arg(long = "ip")]
+    pub ip: Option<String>,
+
+    /// which IP versions to show the address of
+    #[arg(long = "ip-version", value_enum, default_value_t)]
+    pub ip_version: IpVersion,
+
+    /// hide the host part of the IP addresses, e.g. 10.0.x.x, for screen sharing
+    #[arg(long = "ip-mask")]
+    pub ip_mask: bool,
+
+    /// which network interface to show the download and upload rates of, e.g. wlan0 (in /sys/class/net), or `all`
+    /// for every physical interface
+    #[arg(long = "net")]
+    pub net: Option<String>,
+
+    /// show the last rates as sparklines too
+    #[arg(long = "net-spark")]
+    pub net_spark: bool,
 
     /// which wifi card to use, e.g. wlan0 (in /sys/class/net)
     #[arg(short = 'w', long = "wifi")]
     pub wifi: Option<String>,
+
+    /// how to show the RAM usage
+    #[arg(long = "mem", value_enum, default_value_t)]
+    pub memory_format: MemoryFormat,
+
+    /// count the page cache and buffers as used RAM (by default, only memory that can't be reclaimed is counted)
+    #[arg(long = "mem-include-cache")]
+    pub memory_include_cache: bool,
+
+    /// show the RAM usage as a bar too
+    #[arg(long = "mem-bar")]
+    pub memory_bar: bool,
+
+    /// RAM usage percentage from which the label turns red
+    #[arg(long = "mem-warn", default_value_t = 90)]
+    pub memory_warn: u8,
+
+    /// show swap usage, and zram usage if there are zram devices
+    #[arg(long = "swap")]
+    pub swap: bool,
+
+    /// swap usage percentage from which the label turns red
+    #[arg(long = "swap-warn", default_value_t = 50)]
+    pub swap_warn: u8,
+
+    /// which resources to show the pressure stall information of, e.g. cpu,memory,io (in /proc/pressure)
+    #[arg(long = "psi", value_enum, value_delimiter = ',')]
+    pub psi: Vec<Resource>,
+
+    /// show the share of time in which all tasks were stalled, instead of some tasks (older kernels have no such line for cpu, which is then shown as unavailable)
+    #[arg(long = "psi-full")]
+    pub psi_full: bool,
+
+    /// show the pressure of a cgroup instead, e.g. user.slice (relative to /sys/fs/cgroup)
+    #[arg(long = "psi-cgroup")]
+    pub psi_cgroup: Option<String>,
+
+    /// show the pressure as bars
+    #[arg(long = "psi-bar")]
+    pub psi_bar: bool,
+
+    /// pressure percentage from which the value is shown as warm
+    #[arg(long = "psi-warn", default_value_t = 10.)]
+    pub psi_warn: f32,
+
+    /// pressure percentage from which the value is shown as hot
+    #[arg(long = "psi-crit", default_value_t = 40.)]
+    pub psi_crit: f32,
+
+    /// show the load averages and the running/total task counts (from /proc/loadavg)
+    #[arg(long = "load")]
+    pub load: bool,
+
+    /// divide the load averages by the number of cpus
+    #[arg(long = "load-normalize")]
+    pub load_normalize: bool,
+
+    /// run a command when a metric crosses a threshold, e.g. `battery<15:notify-send "low battery"`.
+    /// the format is METRIC<THRESHOLD[~HYSTERESIS][@COOLDOWN]:COMMAND (or with >), where METRIC is one of battery, temperature, memory, load.
+    /// a hook fires once per crossing, and is re-armed after going back past the threshold by HYSTERESIS (default 1).
+    /// COOLDOWN is the minimum number of seconds between two runs (default 0)
+    #[arg(long = "hook")]
+    pub hooks: Vec<Hook>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// print the health, cycle count and charge thresholds of the batteries (all of them, unless --bat is given)
+    BatteryInfo,
 }
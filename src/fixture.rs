@@ -0,0 +1,95 @@
+//! helpers for tests, which build sysfs and procfs trees in a temporary directory, or answer
+//! netlink requests over a socketpair
+
+use std::fmt;
+use std::fs;
+use std::net::Shutdown;
+use std::os::unix::net::UnixDatagram;
+use std::path::Path;
+use std::thread;
+
+use crate::netlink::{self, Header, Netlink};
+
+/// creates `dir` with a file per attribute, which holds the value and a newline like in sysfs
+pub fn attrs(dir: &Path, attrs: &[(impl AsRef<Path>, impl fmt::Display)]) {
+    fs::create_dir_all(dir).unwrap();
+    for (attr, value) in attrs {
+        fs::write(dir.join(attr), format!("{value}\n")).unwrap();
+    }
+}
+
+/// an hwmon chip at `root/hwmon`, with an `{prefix}{n}_input` per input, and a label for the
+/// inputs that have one
+pub fn hwmon_chip(
+    root: &Path,
+    hwmon: &str,
+    name: &str,
+    prefix: &str,
+    inputs: &[(Option<&str>, impl fmt::Display)],
+) {
+    let mut attrs = vec![("name".to_string(), name.to_string())];
+    for (i, (label, value)) in inputs.iter().enumerate() {
+        let index = i + 1;
+        attrs.push((format!("{prefix}{index}_input"), value.to_string()));
+        if let Some(label) = label {
+            attrs.push((format!("{prefix}{index}_label"), label.to_string()));
+        }
+    }
+    self::attrs(&root.join(hwmon), &attrs);
+}
+
+/// a symlink at `link` to `target`, e.g. a device or driver in sysfs, or an fd in procfs
+pub fn symlink(target: impl AsRef<Path>, link: &Path) {
+    fs::create_dir_all(link.parent().unwrap()).unwrap();
+    std::os::unix::fs::symlink(target, link).unwrap();
+}
+
+/// the text of a component, without the colors
+pub fn plain(shown: &str) -> String {
+    let mut plain = String::new();
+    let mut rest = shown;
+    while let Some((before, tag)) = rest.split_once("%{") {
+        plain.push_str(before);
+        rest = tag.split_once('}').map_or("", |(_, after)| after);
+    }
+    plain + rest
+}
+
+/// answers the requests on a netlink socket from a thread, like the kernel would
+pub struct Responder {
+    socket: UnixDatagram,
+    thread: thread::JoinHandle<()>,
+}
+
+impl Responder {
+    /// `answer` gets the header and payload of the first message of every request, and returns
+    /// the datagram to send back
+    pub fn spawn(
+        mut answer: impl FnMut(Header, &[u8]) -> Vec<u8> + Send + 'static,
+    ) -> (Netlink, Self) {
+        let (ours, theirs) = UnixDatagram::pair().unwrap();
+        let socket = theirs.try_clone().unwrap();
+
+        let thread = thread::spawn(move || {
+            let mut buf = [0; 1024];
+            // until the socket is shut down
+            loop {
+                let len = theirs.recv(&mut buf).unwrap_or(0);
+                if len == 0 {
+                    break;
+                }
+
+                let (header, payload) = netlink::messages(&buf[..len]).next().unwrap();
+                theirs.send(&answer(header, payload)).unwrap();
+            }
+        });
+
+        (Netlink::from_fd(ours.into()), Self { socket, thread })
+    }
+
+    /// closing our end doesn't wake up a datagram socket, so shut theirs down
+    pub fn stop(self) {
+        self.socket.shutdown(Shutdown::Both).unwrap();
+        self.thread.join().unwrap();
+    }
+}
@@ -9,7 +9,6 @@ use std::time::{Duration, Instant};
 use clap::Parser as _;
 use mio::Interest;
 use mio::unix::SourceFd;
-use sysinfo::System;
 use wayland_client::backend::ObjectId;
 use wayland_client::protocol::wl_registry;
 use wayland_client::{Connection, Dispatch, event_created_child};
@@ -23,9 +22,15 @@ use wayland_protocols_wlr::foreign_toplevel::v1::client::zwlr_foreign_toplevel_m
 mod color;
 mod component;
 mod config;
+#[cfg(test)]
+mod fixture;
+mod metrics;
+mod netlink;
+mod sysfs;
 
 use crate::color::Color;
-use crate::config::Config;
+use crate::config::{Command, Config};
+use crate::metrics::Metrics;
 use component::*;
 
 pub const FG: Color = Color(0x888888);
@@ -34,9 +39,17 @@ pub const HEIGHT: f32 = 24.;
 
 const INTERVAL: Duration = Duration::from_secs(2);
 const WAYLAND_TOKEN: mio::Token = mio::Token(1);
+const ADDRESS_TOKEN: mio::Token = mio::Token(2);
 const TITLE_LIMIT: usize = 60;
 
-pub static SYS: LazyLock<Mutex<System>> = LazyLock::new(Default::default);
+/// forced at startup, so that /proc being unreadable is reported like the other startup errors
+pub static SYS: LazyLock<Mutex<Metrics>> = LazyLock::new(|| match Metrics::new() {
+    Ok(metrics) => Mutex::new(metrics),
+    Err(err) => {
+        eprintln!("ERROR: unable to open /proc: {err}");
+        std::process::exit(1);
+    }
+});
 
 fn label(text: &str) -> impl fmt::Display {
     Fg(Color::YELLOW).chain(text)
@@ -50,18 +63,59 @@ const fn reset_bg() -> impl fmt::Display {
     Bg(BG)
 }
 
-// the structure of the bar, excluding the focused window name
-fn build_bar(config: &Config) -> Result<impl fmt::Display, component::Error> {
+// the structure of the bar, excluding the focused window name, along with the address
+// notifications to poll
+fn build_bar(
+    config: &Config,
+) -> Result<(impl fmt::Display, Option<AddressEvents>), component::Error> {
+    for hook in &config.hooks {
+        let (enabled, flag) = match hook.metric {
+            Metric::Battery => (!config.battery.is_empty(), "--bat"),
+            Metric::Temperature => (!config.thermal.is_empty(), "--thermal"),
+            // RAM is always shown
+            Metric::Memory => (true, ""),
+            Metric::Load => (config.load, "--load"),
+        };
+        if !enabled {
+            return Err(DisabledMetric {
+                metric: hook.metric,
+                flag,
+            }
+            .into());
+        }
+    }
+
     let middle = AlignCenter.chain(reset_fg()).chain(reset_bg()).chain(Time);
 
-    let gpu = if let Some(name) = config.gpu.as_deref() {
-        Some(Bg(USAGE_BG).chain(Gpu::new(name)?))
+    let gpu = if let Some(spec) = config.gpu.as_deref() {
+        Some(Bg(USAGE_BG).chain(Gpus::create(spec, &config.gpu_fields)?))
     } else {
         None
     };
 
-    let thermal = if let Some(name) = config.thermal.as_deref() {
-        Some("  ".chain(Temperature::create(name)?).chain(reset_fg()))
+    let gpu_top = config.gpu_top.then(|| {
+        let top = GpuTop::new(Duration::from_secs(config.gpu_top_interval));
+        " ".chain(label("TOP ")).chain(reset_fg()).chain(top)
+    });
+
+    let thermal = if config.thermal.is_empty() {
+        None
+    } else {
+        let hooks = Hooks::new(&config.hooks, Metric::Temperature);
+        let mut temperature = Temperature::create(&config.thermal)?
+            .with_hooks(hooks)
+            .with_unit(config.thermal_unit)
+            .with_thresholds(config.thermal_warm, config.thermal_hot);
+        if config.thermal_bar {
+            temperature = temperature.with_bar(config.thermal_idle);
+        }
+
+        Some("  ".chain(temperature).chain(reset_fg()))
+    };
+
+    let fan = if let Some(spec) = config.fan.as_deref() {
+        let fan = Fan::create(spec, config.fan_bar)?;
+        Some("  ".chain(label("FAN ")).chain(reset_fg()).chain(fan))
     } else {
         None
     };
@@ -76,39 +130,132 @@ fn build_bar(config: &Config) -> Result<impl fmt::Display, component::Error> {
         None
     };
 
-    let battery = if let Some(name) = config.battery.as_deref() {
+    let network = if let Some(interface) = config.link.as_deref() {
+        let network = Network::new(interface)?;
+        Some("  ".chain(label("LINK ")).chain(reset_fg()).chain(network))
+    } else {
+        None
+    };
+
+    let mut address_events = None;
+    let address = if let Some(interface) = config.ip.as_deref() {
+        let mut address = Address::new(interface, config.ip_version, config.ip_mask)?;
+        // without notifications, the addresses are re-read every tick instead
+        address_events = address.subscribe().ok();
+        Some("  ".chain(label("IP ")).chain(reset_fg()).chain(address))
+    } else {
+        None
+    };
+
+    let throughput = if let Some(interface) = config.net.as_deref() {
+        let throughput = Throughput::new(interface, config.net_spark)?;
         Some(
-            "  ".chain(label("BAT "))
-                .chain(reset_fg().chain(Battery::new(name)?)),
+            "  ".chain(label("NET "))
+                .chain(reset_fg())
+                .chain(throughput),
         )
     } else {
         None
     };
 
+    let battery = if config.battery.is_empty() {
+        None
+    } else {
+        let hooks = Hooks::new(&config.hooks, Metric::Battery);
+        let battery = Battery::new(&config.battery, config.battery_each, config.battery_detail)?
+            .with_hooks(hooks);
+        Some("  ".chain(label("BAT ")).chain(reset_fg().chain(battery)))
+    };
+
+    let power = config.power.then(|| {
+        let power = Power::new(&config.battery, config.power_warn, config.power_crit);
+        "  ".chain(label("PWR ")).chain(reset_fg()).chain(power)
+    });
+
+    let peripherals = config.peripherals.then(|| {
+        let recent = Duration::from_secs(config.peripherals_recent);
+        Peripherals::new(config.peripherals_low, recent)
+    });
+
+    let memory = Memory::new(
+        config.memory_format,
+        config.memory_include_cache,
+        config.memory_bar,
+        config.memory_warn,
+    )
+    .with_hooks(Hooks::new(&config.hooks, Metric::Memory));
+
+    let swap = config.swap.then(|| "  ".chain(Swap::new(config.swap_warn)));
+
+    let pressure = if config.psi.is_empty() {
+        None
+    } else {
+        let pressure = Pressure::new(
+            &config.psi,
+            config.psi_cgroup.as_deref(),
+            config.psi_full,
+            config.psi_bar,
+            config.psi_warn,
+            config.psi_crit,
+        )?;
+        Some("  ".chain(pressure))
+    };
+
+    let load = if config.load {
+        let hooks = Hooks::new(&config.hooks, Metric::Load);
+        let load = Load::new(config.load_normalize)?.with_hooks(hooks);
+        Some("  ".chain(label("LOAD ")).chain(load).chain(reset_fg()))
+    } else {
+        None
+    };
+
     let right = AlignRight
         .chain(DisplayOption(gpu))
         .chain(reset_bg())
+        .chain(DisplayOption(gpu_top))
         .chain(" ")
         .chain(Bg(USAGE_BG))
         .chain(Cpu)
         .chain(reset_fg())
         .chain(reset_bg())
         .chain(DisplayOption(thermal))
-        .chain("  ".chain(label("RAM ")).chain(reset_fg()).chain(Memory))
+        .chain(DisplayOption(fan))
+        .chain(DisplayOption(load))
+        .chain(DisplayOption(pressure))
+        .chain("  ".chain(memory))
+        .chain(DisplayOption(swap))
         .chain(DisplayOption(wifi))
-        .chain(DisplayOption(battery));
-
-    Ok(middle.chain(right))
+        .chain(DisplayOption(network))
+        .chain(DisplayOption(address))
+        .chain(DisplayOption(throughput))
+        .chain(DisplayOption(battery))
+        .chain(DisplayOption(power))
+        .chain(DisplayOption(peripherals));
+
+    Ok((middle.chain(right), address_events))
 }
 
 fn main() {
     let config = Config::parse();
 
-    let bar = match build_bar(&config) {
+    if let Some(Command::BatteryInfo) = config.command {
+        match BatteryInfo::new(&config.battery) {
+            Ok(info) => print!("{info}"),
+            Err(err) => {
+                eprintln!("ERROR: {err}");
+                std::process::exit(1);
+            }
+        }
+
+        return;
+    }
+
+    LazyLock::force(&SYS);
+    let (bar, address_events) = match build_bar(&config) {
         Ok(bar) => bar,
         Err(err) => {
             eprintln!("ERROR: {err}");
-            return;
+            std::process::exit(1);
         }
     };
 
@@ -136,7 +283,7 @@ fn main() {
 
     // used for efficiently polling from the wayland socket
     let mut poll = mio::Poll::new().expect("unable to create Poll instance");
-    let mut events = mio::Events::with_capacity(1);
+    let mut events = mio::Events::with_capacity(2);
 
     // some variables to make sure the bar is rendered every INTERVAL time, except for the title
     // which updates instantly
@@ -155,6 +302,18 @@ fn main() {
             .unwrap();
     }
 
+    // and the address notifications
+    if let Some(address_events) = &address_events {
+        let address_fd = address_events.as_raw_fd();
+        poll.registry()
+            .register(
+                &mut SourceFd(&address_fd),
+                ADDRESS_TOKEN,
+                Interest::READABLE,
+            )
+            .unwrap();
+    }
+
     loop {
         // dispatch pending messages on queue
         event_queue.flush().unwrap();
@@ -167,6 +326,13 @@ fn main() {
         events.clear();
         poll.poll(&mut events, Some(duration_left)).ok();
 
+        // address changes are shown on the next update, so the other components keep their interval
+        if let Some(address_events) = &address_events
+            && events.iter().any(|event| event.token() == ADDRESS_TOKEN)
+        {
+            address_events.handle();
+        }
+
         // if the interval has already passed, update content
         if last_update.elapsed() > duration_left {
             output.clear();
@@ -176,9 +342,8 @@ fn main() {
             duration_left = INTERVAL;
         }
 
-        // if there was some event, then it's probably from wayland
-        // dispatch the pending messages
-        if !events.is_empty() && read_guard.read().is_ok() {
+        // if there was some event from wayland, dispatch the pending messages
+        if events.iter().any(|event| event.token() == WAYLAND_TOKEN) && read_guard.read().is_ok() {
             event_queue.dispatch_pending(&mut windows).unwrap();
             event_queue.roundtrip(&mut windows).unwrap();
         }
@@ -0,0 +1,102 @@
+use std::fs::File;
+use std::os::unix::fs::FileExt as _;
+use std::path::Path;
+use std::{io, str};
+
+#[cfg(not(feature = "sysinfo"))]
+mod procfs;
+#[cfg(feature = "sysinfo")]
+mod sysinfo;
+
+#[cfg(feature = "sysinfo")]
+pub use self::sysinfo::Metrics;
+#[cfg(not(feature = "sysinfo"))]
+pub use procfs::Metrics;
+
+/// memory statistics, in bytes
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MemInfo {
+    pub total: u64,
+    pub free: u64,
+    pub available: u64,
+    pub swap_total: u64,
+    pub swap_free: u64,
+}
+
+impl MemInfo {
+    /// from the contents of /proc/meminfo
+    #[cfg(any(test, not(feature = "sysinfo")))]
+    pub fn parse(meminfo: &str) -> io::Result<Self> {
+        let mut memory = Self::default();
+        for line in meminfo.lines() {
+            let mut words = line.split_ascii_whitespace();
+            let (Some(key), Some(value)) = (words.next(), words.next()) else {
+                continue;
+            };
+
+            let field = match key {
+                "MemTotal:" => &mut memory.total,
+                "MemFree:" => &mut memory.free,
+                "MemAvailable:" => &mut memory.available,
+                "SwapTotal:" => &mut memory.swap_total,
+                "SwapFree:" => &mut memory.swap_free,
+                _ => continue,
+            };
+
+            // the values are in KiB
+            let kb: u64 = value.parse().map_err(io::Error::other)?;
+            *field = kb << 10;
+        }
+
+        Ok(memory)
+    }
+
+    /// memory that can't be reclaimed, excluding caches
+    pub fn used(&self) -> u64 {
+        self.total.saturating_sub(self.available)
+    }
+
+    /// memory that isn't free, including caches and buffers
+    pub fn used_with_cache(&self) -> u64 {
+        self.total.saturating_sub(self.free)
+    }
+
+    pub fn swap_used(&self) -> u64 {
+        self.swap_total.saturating_sub(self.swap_free)
+    }
+}
+
+/// a file under /proc that is kept open and re-read from the start on every read
+pub struct ProcFile {
+    file: File,
+    buf: Vec<u8>,
+}
+
+impl ProcFile {
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let file = File::open(path)?;
+        Ok(Self {
+            file,
+            buf: vec![0; 4096],
+        })
+    }
+
+    /// reads the whole file using pread at offset 0, reusing the internal buffer
+    pub fn read(&mut self) -> io::Result<&str> {
+        let mut filled = 0;
+        loop {
+            if filled == self.buf.len() {
+                self.buf.resize(self.buf.len() * 2, 0);
+            }
+
+            let n = self.file.read_at(&mut self.buf[filled..], filled as u64)?;
+            if n == 0 {
+                break;
+            }
+
+            filled += n;
+        }
+
+        str::from_utf8(&self.buf[..filled]).map_err(io::Error::other)
+    }
+}
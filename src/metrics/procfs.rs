@@ -0,0 +1,177 @@
+use std::io;
+
+use super::{MemInfo, ProcFile};
+
+const STAT: &str = "/proc/stat";
+const MEMINFO: &str = "/proc/meminfo";
+
+/// cpu and memory statistics read directly from /proc
+pub struct Metrics {
+    stat: ProcFile,
+    meminfo: ProcFile,
+    cpu_times: Vec<CpuTimes>,
+    cpu_usage: Vec<f32>,
+    memory: MemInfo,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+struct CpuTimes {
+    total: u64,
+    idle: u64,
+}
+
+impl CpuTimes {
+    /// in percents
+    fn usage_since(self, prev: Self) -> f32 {
+        let total_delta = self.total.saturating_sub(prev.total);
+        let idle_delta = self.idle.saturating_sub(prev.idle);
+        if total_delta == 0 {
+            0.
+        } else {
+            total_delta.saturating_sub(idle_delta) as f32 * 100. / total_delta as f32
+        }
+    }
+}
+
+impl Metrics {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            stat: ProcFile::open(STAT)?,
+            meminfo: ProcFile::open(MEMINFO)?,
+            cpu_times: Vec::new(),
+            cpu_usage: Vec::new(),
+            memory: MemInfo::default(),
+        })
+    }
+
+    pub fn refresh_cpu_usage(&mut self) -> io::Result<()> {
+        let text = self.stat.read()?;
+
+        let mut index = 0;
+        for times in core_times(text) {
+            let times = times?;
+
+            if index == self.cpu_times.len() {
+                self.cpu_times.push(CpuTimes::default());
+                self.cpu_usage.push(0.);
+            }
+
+            self.cpu_usage[index] = times.usage_since(self.cpu_times[index]);
+            self.cpu_times[index] = times;
+            index += 1;
+        }
+
+        // in case some cores went offline
+        self.cpu_times.truncate(index);
+        self.cpu_usage.truncate(index);
+
+        Ok(())
+    }
+
+    /// usage of each core in percents
+    pub fn cpu_usage(&self) -> &[f32] {
+        &self.cpu_usage
+    }
+
+    pub fn refresh_memory(&mut self) -> io::Result<()> {
+        self.memory = MemInfo::parse(self.meminfo.read()?)?;
+        Ok(())
+    }
+
+    pub fn memory(&self) -> &MemInfo {
+        &self.memory
+    }
+}
+
+/// the times of every core in /proc/stat
+fn core_times(stat: &str) -> impl Iterator<Item = io::Result<CpuTimes>> {
+    stat.lines()
+        .map_while(|line| line.strip_prefix("cpu"))
+        // only the per-core lines (cpu0, cpu1, ...), skipping the aggregated `cpu` line
+        .filter(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        .map(|rest| {
+            // cpuN user nice system idle iowait irq softirq steal guest guest_nice
+            // guest time is already accounted in user time, so only the first 8 are summed
+            let mut times = CpuTimes::default();
+            for (i, value) in rest.split_ascii_whitespace().skip(1).take(8).enumerate() {
+                let value: u64 = value.parse().map_err(io::Error::other)?;
+                times.total += value;
+                if i == 3 || i == 4 {
+                    times.idle += value;
+                }
+            }
+            Ok(times)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STAT: &str = "\
+cpu  4705 356 584 3699176 23060 0 277 0 0 0
+cpu0 1393 280 259 925478 6232 0 220 0 0 0
+cpu1 1105 21 112 924906 5613 0 17 0 0 0
+intr 1462898 0 9 0 0 0 0 0 0 1 0 0 0 0 0 0 0 0
+ctxt 2148562
+btime 1729324800
+processes 9461
+procs_running 2
+procs_blocked 0
+softirq 1168394 0 223071 28 30201 49720 0 2197 452110 0 411067
+";
+
+    const MEMINFO: &str = "\
+MemTotal:       16279408 kB
+MemFree:         9382324 kB
+MemAvailable:   12806496 kB
+Buffers:          318404 kB
+Cached:          3343740 kB
+SwapCached:            0 kB
+Active:          4134300 kB
+SwapTotal:       8388604 kB
+SwapFree:        8388604 kB
+HugePages_Total:       0
+Hugepagesize:       2048 kB
+";
+
+    #[test]
+    fn stat() {
+        let times: Vec<_> = core_times(STAT).collect::<io::Result<_>>().unwrap();
+        assert_eq!(
+            times,
+            [
+                CpuTimes {
+                    total: 1393 + 280 + 259 + 925478 + 6232 + 220,
+                    idle: 925478 + 6232,
+                },
+                CpuTimes {
+                    total: 1105 + 21 + 112 + 924906 + 5613 + 17,
+                    idle: 924906 + 5613,
+                },
+            ]
+        );
+
+        // 100 jiffies, of which 25 idle
+        let later = CpuTimes {
+            total: times[0].total + 100,
+            idle: times[0].idle + 25,
+        };
+        assert_eq!(later.usage_since(times[0]), 75.);
+        assert_eq!(times[0].usage_since(times[0]), 0.);
+
+        assert!(core_times("cpu0 1 x 3\n").next().unwrap().is_err());
+    }
+
+    #[test]
+    fn meminfo() {
+        let memory = MemInfo::parse(MEMINFO).unwrap();
+        assert_eq!(memory.total, 16279408 << 10);
+        assert_eq!(memory.free, 9382324 << 10);
+        assert_eq!(memory.available, 12806496 << 10);
+        assert_eq!(memory.used(), (16279408 - 12806496) << 10);
+        assert_eq!(memory.swap_used(), 0);
+
+        assert!(MemInfo::parse("MemTotal: lots kB\n").is_err());
+    }
+}
@@ -0,0 +1,52 @@
+use std::io;
+
+use sysinfo::{MemoryRefreshKind, System};
+
+use super::MemInfo;
+
+/// cpu and memory statistics, backed by the sysinfo crate
+pub struct Metrics {
+    sys: System,
+    cpu_usage: Vec<f32>,
+    memory: MemInfo,
+}
+
+impl Metrics {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            sys: System::new(),
+            cpu_usage: Vec::new(),
+            memory: MemInfo::default(),
+        })
+    }
+
+    pub fn refresh_cpu_usage(&mut self) -> io::Result<()> {
+        self.sys.refresh_cpu_usage();
+        self.cpu_usage.clear();
+        self.cpu_usage
+            .extend(self.sys.cpus().iter().map(|cpu| cpu.cpu_usage()));
+        Ok(())
+    }
+
+    /// usage of each core in percents
+    pub fn cpu_usage(&self) -> &[f32] {
+        &self.cpu_usage
+    }
+
+    pub fn refresh_memory(&mut self) -> io::Result<()> {
+        self.sys
+            .refresh_memory_specifics(MemoryRefreshKind::nothing().with_ram().with_swap());
+        self.memory = MemInfo {
+            total: self.sys.total_memory(),
+            free: self.sys.free_memory(),
+            available: self.sys.available_memory(),
+            swap_total: self.sys.total_swap(),
+            swap_free: self.sys.free_swap(),
+        };
+        Ok(())
+    }
+
+    pub fn memory(&self) -> &MemInfo {
+        &self.memory
+    }
+}
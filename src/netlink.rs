@@ -0,0 +1,354 @@
+use std::cell::Cell;
+use std::os::fd::{AsRawFd, FromRawFd as _, OwnedFd, RawFd};
+use std::{io, mem};
+
+pub const NLMSG_ERROR: u16 = 2;
+pub const NLMSG_DONE: u16 = 3;
+
+pub const NLM_F_REQUEST: u16 = 0x1;
+pub const NLM_F_MULTI: u16 = 0x2;
+pub const NLM_F_DUMP: u16 = 0x300;
+
+const HEADER_LEN: usize = 16;
+const ATTR_HEADER_LEN: usize = 4;
+
+// enough for the dumps we make, which are a few small messages
+const RECV_SIZE: usize = 32 * 1024;
+
+// the kernel answers right away, and the requests are made while rendering the bar
+const RECV_TIMEOUT_US: libc::suseconds_t = 50_000;
+
+/// netlink messages and attributes are aligned to 4 bytes
+const fn align(len: usize) -> usize {
+    (len + 3) & !3
+}
+
+/// a netlink socket for request/response exchanges, e.g. with generic netlink.
+/// the fd can be anything with datagram semantics, so that tests can answer over a socketpair.
+pub struct Netlink {
+    fd: OwnedFd,
+    seq: Cell<u32>,
+}
+
+impl Netlink {
+    fn socket(protocol: i32, flags: i32) -> io::Result<OwnedFd> {
+        let fd = unsafe {
+            libc::socket(
+                libc::AF_NETLINK,
+                libc::SOCK_DGRAM | libc::SOCK_CLOEXEC | flags,
+                protocol,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+    }
+
+    /// `protocol` is e.g. NETLINK_GENERIC
+    pub fn open(protocol: i32) -> io::Result<Self> {
+        let fd = Self::socket(protocol, 0)?;
+
+        // never block the bar for long on an answer that doesn't come
+        let timeout = libc::timeval {
+            tv_sec: 0,
+            tv_usec: RECV_TIMEOUT_US,
+        };
+        let res = unsafe {
+            libc::setsockopt(
+                fd.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                (&raw const timeout).cast(),
+                mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self::from_fd(fd))
+    }
+
+    /// a non-blocking socket that receives the notifications of the multicast `groups`, a bitmask of
+    /// e.g. RTMGRP_IPV4_IFADDR, to be polled through its fd
+    pub fn subscribe(protocol: i32, groups: u32) -> io::Result<Self> {
+        let fd = Self::socket(protocol, libc::SOCK_NONBLOCK)?;
+
+        let mut addr: libc::sockaddr_nl = unsafe { mem::zeroed() };
+        addr.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        addr.nl_groups = groups;
+        let res = unsafe {
+            libc::bind(
+                fd.as_raw_fd(),
+                (&raw const addr).cast(),
+                mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self::from_fd(fd))
+    }
+
+    pub fn from_fd(fd: OwnedFd) -> Self {
+        Self {
+            fd,
+            seq: Cell::new(1),
+        }
+    }
+
+    pub fn send(&self, buf: &[u8]) -> io::Result<()> {
+        let res = unsafe { libc::send(self.fd.as_raw_fd(), buf.as_ptr().cast(), buf.len(), 0) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+
+    pub fn recv(&self, buf: &mut [u8]) -> io::Result<usize> {
+        let res = unsafe { libc::recv(self.fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len(), 0) };
+        if res < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(res as usize)
+    }
+
+    /// reads all of the pending datagrams of a non-blocking socket, passing each one to `f`
+    pub fn drain(&self, mut f: impl FnMut(&[u8])) -> io::Result<()> {
+        let mut buf = vec![0; RECV_SIZE];
+        loop {
+            match self.recv(&mut buf) {
+                Ok(0) => return Ok(()),
+                Ok(len) => f(&buf[..len]),
+                Err(err) if err.kind() == io::ErrorKind::WouldBlock => return Ok(()),
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// sends `message` and collects the payloads of the answers, until the end of a dump, an ack
+    /// or a single answer
+    pub fn request(&self, mut message: Message) -> io::Result<Vec<Vec<u8>>> {
+        let seq = self.seq.get();
+        self.seq.set(seq.wrapping_add(1));
+        self.send(message.finish(seq))?;
+
+        let mut payloads = Vec::new();
+        let mut buf = vec![0; RECV_SIZE];
+        loop {
+            let len = self.recv(&mut buf)?;
+            if len == 0 {
+                return Err(io::ErrorKind::UnexpectedEof.into());
+            }
+
+            for (header, payload) in messages(&buf[..len]) {
+                if header.seq != seq {
+                    continue;
+                }
+
+                match header.kind {
+                    NLMSG_DONE => return Ok(payloads),
+                    NLMSG_ERROR => {
+                        let errno = payload
+                            .get(..4)
+                            .map(|errno| i32::from_ne_bytes(errno.try_into().unwrap()))
+                            .ok_or(io::ErrorKind::InvalidData)?;
+                        if errno != 0 {
+                            return Err(io::Error::from_raw_os_error(-errno));
+                        }
+                        // an ack
+                        return Ok(payloads);
+                    }
+                    _ => payloads.push(payload.to_vec()),
+                }
+
+                if header.flags & NLM_F_MULTI == 0 {
+                    return Ok(payloads);
+                }
+            }
+        }
+    }
+}
+
+impl AsRawFd for Netlink {
+    fn as_raw_fd(&self) -> RawFd {
+        self.fd.as_raw_fd()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Header {
+    pub kind: u16,
+    pub flags: u16,
+    pub seq: u32,
+}
+
+/// the messages in a datagram, along with their payload
+pub fn messages(mut buf: &[u8]) -> impl Iterator<Item = (Header, &[u8])> {
+    std::iter::from_fn(move || {
+        if buf.len() < HEADER_LEN {
+            return None;
+        }
+
+        let len = u32::from_ne_bytes(buf[0..4].try_into().unwrap()) as usize;
+        if len < HEADER_LEN || len > buf.len() {
+            return None;
+        }
+
+        let header = Header {
+            kind: u16::from_ne_bytes(buf[4..6].try_into().unwrap()),
+            flags: u16::from_ne_bytes(buf[6..8].try_into().unwrap()),
+            seq: u32::from_ne_bytes(buf[8..12].try_into().unwrap()),
+        };
+        let payload = &buf[HEADER_LEN..len];
+        buf = &buf[align(len).min(buf.len())..];
+
+        Some((header, payload))
+    })
+}
+
+/// the attributes in a payload, as (type, value). the nested and byte order flags are removed from the type
+pub fn attrs(mut buf: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        if buf.len() < ATTR_HEADER_LEN {
+            return None;
+        }
+
+        let len = u16::from_ne_bytes(buf[0..2].try_into().unwrap()) as usize;
+        if len < ATTR_HEADER_LEN || len > buf.len() {
+            return None;
+        }
+
+        let kind = u16::from_ne_bytes(buf[2..4].try_into().unwrap()) & 0x3fff;
+        let value = &buf[ATTR_HEADER_LEN..len];
+        buf = &buf[align(len).min(buf.len())..];
+
+        Some((kind, value))
+    })
+}
+
+/// a message under construction
+pub struct Message {
+    buf: Vec<u8>,
+}
+
+impl Message {
+    pub fn new(kind: u16, flags: u16) -> Self {
+        let mut buf = vec![0; HEADER_LEN];
+        buf[4..6].copy_from_slice(&kind.to_ne_bytes());
+        buf[6..8].copy_from_slice(&flags.to_ne_bytes());
+        Self { buf }
+    }
+
+    /// a fixed-size family header, e.g. genlmsghdr
+    pub fn push(mut self, bytes: &[u8]) -> Self {
+        self.buf.extend_from_slice(bytes);
+        self.buf.resize(align(self.buf.len()), 0);
+        self
+    }
+
+    pub fn attr(mut self, kind: u16, value: &[u8]) -> Self {
+        let len = (ATTR_HEADER_LEN + value.len()) as u16;
+        self.buf.extend_from_slice(&len.to_ne_bytes());
+        self.buf.extend_from_slice(&kind.to_ne_bytes());
+        self.push(value)
+    }
+
+    fn finish(&mut self, seq: u32) -> &[u8] {
+        let len = self.buf.len() as u32;
+        self.buf[0..4].copy_from_slice(&len.to_ne_bytes());
+        self.buf[8..12].copy_from_slice(&seq.to_ne_bytes());
+        &self.buf
+    }
+
+    /// the message as an answer to `seq`, for responders in tests
+    #[cfg(test)]
+    pub fn answer(mut self, seq: u32) -> Vec<u8> {
+        self.finish(seq);
+        self.buf
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::os::unix::net::UnixDatagram;
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn attributes() {
+        let message = Message::new(16, NLM_F_REQUEST)
+            .push(&[3, 1, 0, 0])
+            .attr(2, b"nl80211\0")
+            .attr(1 | 0x8000, &[1, 2, 3])
+            .attr(3, &[])
+            .answer(7);
+
+        let (header, payload) = messages(&message).next().unwrap();
+        assert_eq!(
+            header,
+            Header {
+                kind: 16,
+                flags: NLM_F_REQUEST,
+                seq: 7
+            }
+        );
+
+        let attrs: Vec<_> = attrs(&payload[4..]).collect();
+        assert_eq!(
+            attrs,
+            [(2, &b"nl80211\0"[..]), (1, &[1, 2, 3][..]), (3, &[][..])]
+        );
+    }
+
+    #[test]
+    fn dump_and_errors() {
+        let (ours, theirs) = UnixDatagram::pair().unwrap();
+        let netlink = Netlink::from_fd(ours.into());
+
+        let responder = thread::spawn(move || {
+            let mut buf = [0; 1024];
+
+            // a dump in two datagrams, with an unrelated message in between
+            let len = theirs.recv(&mut buf).unwrap();
+            let (header, _) = messages(&buf[..len]).next().unwrap();
+            let mut datagram = Message::new(20, NLM_F_MULTI)
+                .attr(1, b"a")
+                .answer(header.seq);
+            datagram.extend(Message::new(20, NLM_F_MULTI).attr(1, b"b").answer(99));
+            theirs.send(&datagram).unwrap();
+            let mut datagram = Message::new(20, NLM_F_MULTI)
+                .attr(1, b"c")
+                .answer(header.seq);
+            datagram.extend(Message::new(NLMSG_DONE, NLM_F_MULTI).answer(header.seq));
+            theirs.send(&datagram).unwrap();
+
+            // an error
+            let len = theirs.recv(&mut buf).unwrap();
+            let (header, _) = messages(&buf[..len]).next().unwrap();
+            let error = Message::new(NLMSG_ERROR, 0)
+                .push(&(-libc::ENODEV).to_ne_bytes())
+                .answer(header.seq);
+            theirs.send(&error).unwrap();
+        });
+
+        let payloads = netlink
+            .request(Message::new(20, NLM_F_REQUEST | NLM_F_DUMP))
+            .unwrap();
+        let values: Vec<_> = payloads
+            .iter()
+            .flat_map(|payload| attrs(payload).map(|(_, value)| value.to_vec()))
+            .collect();
+        assert_eq!(values, [b"a", b"c"]);
+
+        let err = netlink
+            .request(Message::new(20, NLM_F_REQUEST))
+            .unwrap_err();
+        assert_eq!(err.raw_os_error(), Some(libc::ENODEV));
+
+        responder.join().unwrap();
+    }
+}
@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+use std::fs::File;
+use std::os::unix::fs::FileExt as _;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::{io, str};
+
+// sysfs attributes are at most a single page
+const PAGE_SIZE: usize = 4096;
+
+/// a sysfs attribute that is opened once and re-read using pread at offset 0.
+/// when the underlying device disappears (e.g. a re-plugged battery), the file is re-opened.
+pub struct SysfsAttr {
+    path: PathBuf,
+    file: RefCell<File>,
+}
+
+impl SysfsAttr {
+    pub fn open(path: impl Into<PathBuf>) -> io::Result<Self> {
+        let path = path.into();
+        let file = File::open(&path)?;
+        Ok(Self {
+            path,
+            file: RefCell::new(file),
+        })
+    }
+
+    /// reads the attribute into a stack buffer and passes the trimmed content to `f`
+    pub fn read_with<T>(&self, f: impl FnOnce(&[u8]) -> T) -> io::Result<T> {
+        let mut buf = [0; PAGE_SIZE];
+        let len = match self.file.borrow().read_at(&mut buf, 0) {
+            Err(err) if is_gone(&err) => None,
+            res => Some(res?),
+        };
+
+        let len = match len {
+            Some(len) => len,
+            None => {
+                let file = File::open(&self.path)?;
+                let len = file.read_at(&mut buf, 0)?;
+                *self.file.borrow_mut() = file;
+                len
+            }
+        };
+
+        Ok(f(buf[..len].trim_ascii()))
+    }
+
+    pub fn read<T: FromStr>(&self) -> io::Result<T> {
+        self.read_with(parse)?.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("failed to parse {}", self.path.display()),
+            )
+        })
+    }
+}
+
+fn is_gone(err: &io::Error) -> bool {
+    matches!(err.raw_os_error(), Some(libc::ENODEV | libc::ENOENT))
+}
+
+/// parses a value without allocating
+pub fn parse<T: FromStr>(bytes: &[u8]) -> Option<T> {
+    str::from_utf8(bytes.trim_ascii()).ok()?.parse().ok()
+}